@@ -0,0 +1,189 @@
+use std::str::FromStr;
+
+use crate::{quantile, QuantileType};
+
+/// Binning picks the bin edges of a reliability diagram, uniform splits [0, 1] into bins of the
+/// same width and quantile puts about the same number of predictions in each bin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binning {
+    Uniform,
+    Quantile,
+}
+
+impl FromStr for Binning {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(Binning::Uniform),
+            "quantile" => Ok(Binning::Quantile),
+            _ => Err(format!(
+                "invalid binning {}, expected uniform or quantile",
+                s
+            )),
+        }
+    }
+}
+
+/// CalibrationBin holds the predictions with a probability in [lower, upper), the last bin
+/// includes its upper edge
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub n: usize,
+    /// the mean predicted probability
+    pub predicted: f64,
+    /// the fraction of the predictions that were right
+    pub observed: f64,
+}
+
+/// Calibration holds a reliability diagram, only bins with predictions are kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub n: usize,
+    pub brier: f64,
+    /// the mean |observed - predicted| of the bins, weighted by their size
+    pub ece: f64,
+    /// the largest |observed - predicted| of any bin
+    pub mce: f64,
+    pub bins: Vec<CalibrationBin>,
+}
+
+/// calibration bins (probability, actual) pairs of a binary prediction, the input must be non
+/// empty
+pub fn calibration(input: &[(f64, bool)], bins: usize, binning: Binning) -> Calibration {
+    let brier = input
+        .iter()
+        .map(|(p, a)| (p - if *a { 1.0 } else { 0.0 }).powi(2))
+        .sum::<f64>()
+        / input.len() as f64;
+
+    reliability(input, bins, binning, brier)
+}
+
+/// calibration_multiclass bins the probability of the most likely class of each row against
+/// whether it was the actual class. The Brier score sums the squared error of every class, so
+/// it ranges from 0 to 2.
+pub fn calibration_multiclass(
+    rows: &[(Vec<f64>, usize)],
+    bins: usize,
+    binning: Binning,
+) -> Calibration {
+    let mut confidence = vec![];
+    let mut brier = 0.0;
+
+    for (probabilities, actual) in rows {
+        let mut top = 0;
+
+        for (class, p) in probabilities.iter().enumerate() {
+            let y = if class == *actual { 1.0 } else { 0.0 };
+            brier += (p - y).powi(2);
+
+            if *p > probabilities[top] {
+                top = class;
+            }
+        }
+
+        confidence.push((probabilities[top], top == *actual));
+    }
+
+    reliability(&confidence, bins, binning, brier / rows.len() as f64)
+}
+
+fn reliability(input: &[(f64, bool)], bins: usize, binning: Binning, brier: f64) -> Calibration {
+    let bins = bins.max(1);
+
+    let edges: Vec<f64> = match binning {
+        Binning::Uniform => (0..=bins).map(|i| i as f64 / bins as f64).collect(),
+        Binning::Quantile => {
+            let mut sorted: Vec<f64> = input.iter().map(|(p, _)| *p).collect();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            (0..=bins)
+                .map(|i| quantile(&sorted, i as f64 / bins as f64, QuantileType::default()))
+                .collect()
+        }
+    };
+
+    // (n, sum of probabilities, right) of each bin, a probability on an inner edge goes up
+    let mut sums = vec![(0, 0.0, 0); bins];
+    for (p, actual) in input {
+        let bin = edges[1..bins].partition_point(|e| e <= p);
+
+        sums[bin].0 += 1;
+        sums[bin].1 += p;
+        if *actual {
+            sums[bin].2 += 1;
+        }
+    }
+
+    let n = input.len();
+    let (mut ece, mut mce) = (0.0, 0.0f64);
+    let mut out = vec![];
+
+    for (i, (count, sum, right)) in sums.into_iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+
+        let predicted = sum / count as f64;
+        let observed = right as f64 / count as f64;
+        let gap = (observed - predicted).abs();
+
+        ece += gap * count as f64 / n as f64;
+        mce = mce.max(gap);
+
+        out.push(CalibrationBin {
+            lower: edges[i],
+            upper: edges[i + 1],
+            n: count,
+            predicted,
+            observed,
+        });
+    }
+
+    Calibration {
+        n,
+        brier,
+        ece,
+        mce,
+        bins: out,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_calibration() {
+        let input = vec![
+            (0.1, false),
+            (0.2, true),
+            (0.8, true),
+            (0.9, true),
+            (1.0, true),
+        ];
+        let c = calibration(&input, 2, Binning::Uniform);
+
+        assert_eq!(c.bins.len(), 2);
+        assert_eq!((c.bins[0].n, c.bins[1].n), (2, 3));
+        assert!((c.bins[0].predicted - 0.15).abs() < 1e-12);
+        assert_eq!(c.bins[0].observed, 0.5);
+        assert!((c.brier - 0.14).abs() < 1e-12);
+        assert!((c.ece - (2.0 * 0.35 + 3.0 * 0.1) / 5.0).abs() < 1e-12);
+        assert!((c.mce - 0.35).abs() < 1e-12);
+
+        // the median, 0.8, is an edge and goes to the upper bin
+        let c = calibration(&input, 2, Binning::Quantile);
+        assert_eq!((c.bins[0].upper, c.bins[0].n), (0.8, 2));
+
+        let rows = vec![(vec![0.6, 0.4], 0), (vec![0.2, 0.8], 0)];
+        let c = calibration_multiclass(&rows, 10, Binning::Uniform);
+        assert_eq!(c.bins.len(), 2);
+        assert!((c.brier - (0.32 + 1.28) / 2.0).abs() < 1e-12);
+        assert!((c.ece - (0.4 + 0.8) / 2.0).abs() < 1e-12);
+    }
+}
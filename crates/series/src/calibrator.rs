@@ -0,0 +1,311 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::SeriesError;
+
+/// Method is how a calibrator is fit, platt fits a logistic curve to the scores and isotonic
+/// fits a non decreasing step function, which needs more data but no assumed shape
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    Platt,
+    Isotonic,
+}
+
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "platt" => Ok(Method::Platt),
+            "isotonic" => Ok(Method::Isotonic),
+            _ => Err(format!("invalid method {}, expected platt or isotonic", s)),
+        }
+    }
+}
+
+/// Calibrator maps the scores of a binary prediction to probabilities of the positive class.
+/// Platt is p = 1 / (1 + exp(a * score + b)). Isotonic interpolates linearly between its
+/// (score, probability) points and is constant past the first and last.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Calibrator {
+    Platt { a: f64, b: f64 },
+    Isotonic { points: Vec<(f64, f64)> },
+}
+
+impl Calibrator {
+    /// fit learns a calibrator from (score, actual) pairs, the input must be non empty
+    pub fn fit(input: &[(f64, bool)], method: Method) -> Calibrator {
+        match method {
+            Method::Platt => platt(input),
+            Method::Isotonic => isotonic(input),
+        }
+    }
+
+    pub fn apply(&self, score: f64) -> f64 {
+        match self {
+            Calibrator::Platt { a, b } => sigmoid(-(a * score + b)),
+            Calibrator::Isotonic { points } => {
+                let i = points.partition_point(|(x, _)| *x <= score);
+
+                if i == 0 {
+                    return points[0].1;
+                }
+
+                if i == points.len() {
+                    return points[i - 1].1;
+                }
+
+                let ((x0, y0), (x1, y1)) = (points[i - 1], points[i]);
+                y0 + (score - x0) / (x1 - x0) * (y1 - y0)
+            }
+        }
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    if x >= 0.0 {
+        1.0 / (1.0 + (-x).exp())
+    } else {
+        x.exp() / (1.0 + x.exp())
+    }
+}
+
+/// platt fits a and b by Newton's method with a backtracking line search, as in Lin, Lin and
+/// Weng's note on Platt's probabilistic outputs. The targets are smoothed by the class counts
+/// so a separable input doesn't fit an infinitely steep curve.
+fn platt(input: &[(f64, bool)]) -> Calibrator {
+    let positives = input.iter().filter(|(_, y)| *y).count() as f64;
+    let negatives = input.len() as f64 - positives;
+
+    let hi = (positives + 1.0) / (positives + 2.0);
+    let lo = 1.0 / (negatives + 2.0);
+    let targets: Vec<f64> = input
+        .iter()
+        .map(|(_, y)| if *y { hi } else { lo })
+        .collect();
+
+    // the negative log likelihood, written so exp can't overflow
+    let loss = |a: f64, b: f64| -> f64 {
+        input
+            .iter()
+            .zip(&targets)
+            .map(|((s, _), t)| {
+                let z = a * s + b;
+                if z >= 0.0 {
+                    t * z + (-z).exp().ln_1p()
+                } else {
+                    (t - 1.0) * z + z.exp().ln_1p()
+                }
+            })
+            .sum()
+    };
+
+    let mut a = 0.0;
+    let mut b = ((negatives + 1.0) / (positives + 1.0)).ln();
+    let mut value = loss(a, b);
+
+    for _ in 0..100 {
+        let (mut h11, mut h22, mut h21) = (1e-12, 1e-12, 0.0);
+        let (mut g1, mut g2) = (0.0, 0.0);
+
+        for ((s, _), t) in input.iter().zip(&targets) {
+            let p = sigmoid(-(a * s + b));
+            let d2 = p * (1.0 - p);
+
+            h11 += s * s * d2;
+            h22 += d2;
+            h21 += s * d2;
+
+            let d1 = t - p;
+            g1 += s * d1;
+            g2 += d1;
+        }
+
+        if g1.abs() < 1e-5 && g2.abs() < 1e-5 {
+            break;
+        }
+
+        let det = h11 * h22 - h21 * h21;
+        let da = -(h22 * g1 - h21 * g2) / det;
+        let db = -(-h21 * g1 + h11 * g2) / det;
+        let gd = g1 * da + g2 * db;
+
+        let mut step = 1.0;
+        while step >= 1e-10 {
+            let next = loss(a + step * da, b + step * db);
+
+            if next < value + 1e-4 * step * gd {
+                a += step * da;
+                b += step * db;
+                value = next;
+                break;
+            }
+
+            step /= 2.0;
+        }
+
+        if step < 1e-10 {
+            break;
+        }
+    }
+
+    Calibrator::Platt { a, b }
+}
+
+/// isotonic fits by pool adjacent violators, tied scores are pooled first so they get the
+/// same probability
+fn isotonic(input: &[(f64, bool)]) -> Calibrator {
+    let mut sorted = input.to_vec();
+    sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // (lowest score, highest score, count, positives) of each block
+    let mut blocks: Vec<(f64, f64, f64, f64)> = vec![];
+
+    for (s, y) in sorted {
+        let y = if y { 1.0 } else { 0.0 };
+
+        match blocks.last_mut() {
+            Some(last) if last.1 == s => {
+                last.2 += 1.0;
+                last.3 += y;
+            }
+            _ => blocks.push((s, s, 1.0, y)),
+        }
+
+        while blocks.len() > 1 {
+            let last = blocks[blocks.len() - 1];
+            let prev = blocks[blocks.len() - 2];
+
+            if prev.3 / prev.2 <= last.3 / last.2 {
+                break;
+            }
+
+            blocks.pop();
+            let prev = blocks.last_mut().unwrap();
+            prev.1 = last.1;
+            prev.2 += last.2;
+            prev.3 += last.3;
+        }
+    }
+
+    let mut points = vec![];
+    for (lower, upper, n, positives) in blocks {
+        points.push((lower, positives / n));
+        if upper > lower {
+            points.push((upper, positives / n));
+        }
+    }
+
+    Calibrator::Isotonic { points }
+}
+
+/// a platt calibrator is written as the line "platt,a,b", an isotonic one as the line
+/// "isotonic" followed by a "score,probability" line for each point
+impl fmt::Display for Calibrator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Calibrator::Platt { a, b } => writeln!(f, "platt,{},{}", a, b),
+            Calibrator::Isotonic { points } => {
+                writeln!(f, "isotonic")?;
+
+                for (x, y) in points {
+                    writeln!(f, "{},{}", x, y)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Calibrator {
+    type Err = SeriesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |line: usize| SeriesError::Calibrator { line };
+        let pair = |line: &str, index: usize| -> Result<(f64, f64), SeriesError> {
+            let (x, y) = line.split_once(',').ok_or_else(|| invalid(index))?;
+            let x = x.trim().parse().map_err(|_| invalid(index))?;
+            let y = y.trim().parse().map_err(|_| invalid(index))?;
+            Ok((x, y))
+        };
+
+        let mut lines = s.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+
+        let header = match lines.next() {
+            Some((_, line)) => line.trim(),
+            None => return Err(SeriesError::Empty),
+        };
+
+        if let Some(rest) = header.strip_prefix("platt,") {
+            let (a, b) = pair(rest, 1)?;
+
+            if lines.next().is_some() {
+                return Err(invalid(2));
+            }
+
+            return Ok(Calibrator::Platt { a, b });
+        }
+
+        if header != "isotonic" {
+            return Err(invalid(1));
+        }
+
+        let mut points: Vec<(f64, f64)> = vec![];
+        for (index, line) in lines {
+            let point = pair(line, index + 1)?;
+
+            if points.last().map(|last| last.0 > point.0).unwrap_or(false) {
+                return Err(invalid(index + 1));
+            }
+
+            points.push(point);
+        }
+
+        if points.is_empty() {
+            return Err(SeriesError::Empty);
+        }
+
+        Ok(Calibrator::Isotonic { points })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_calibrator() {
+        let input = vec![(1.0, true), (2.0, false), (3.0, true), (4.0, true)];
+        let c = Calibrator::fit(&input, Method::Isotonic);
+
+        // 1 and 2 violate the order and are pooled
+        assert_eq!(
+            c,
+            Calibrator::Isotonic {
+                points: vec![(1.0, 0.5), (2.0, 0.5), (3.0, 1.0), (4.0, 1.0)]
+            }
+        );
+        assert_eq!(c.apply(2.5), 0.75);
+        assert_eq!((c.apply(0.0), c.apply(9.0)), (0.5, 1.0));
+        assert_eq!(c.to_string().parse::<Calibrator>().unwrap(), c);
+
+        // the input is symmetric about 0 with the classes swapped
+        let input = vec![
+            (-2.0, false),
+            (-1.0, false),
+            (-0.5, true),
+            (0.5, false),
+            (1.0, true),
+            (2.0, true),
+        ];
+        let c = Calibrator::fit(&input, Method::Platt);
+
+        assert!((c.apply(0.0) - 0.5).abs() < 1e-6);
+        assert!(c.apply(-1.0) < 0.5 && c.apply(1.0) > 0.5);
+        assert_eq!(c.to_string().parse::<Calibrator>().unwrap(), c);
+
+        assert!("isotonic\n1,0.5\n0,0.2\n".parse::<Calibrator>().is_err());
+    }
+}
@@ -0,0 +1,267 @@
+/// McNemar holds McNemar's test of two classifiers on the same rows, it only depends on the
+/// rows where exactly one of them is right
+#[derive(Debug, Clone, PartialEq)]
+pub struct McNemar {
+    /// rows only the first classifier got right
+    pub b: usize,
+    /// rows only the second classifier got right
+    pub c: usize,
+    /// the chi-squared statistic with a continuity correction, (|b - c| - 1)^2 / (b + c)
+    pub statistic: f64,
+    pub p_value: f64,
+    /// the two sided binomial p-value, better than the chi-squared one when b + c is small
+    pub exact_p_value: f64,
+}
+
+/// mcnemar tests whether two classifiers have the same error rate from whether each was
+/// right on each row, the p-values are 1 when they never disagree
+pub fn mcnemar(correct: &[(bool, bool)]) -> McNemar {
+    let b = correct.iter().filter(|(a, b)| *a && !*b).count();
+    let c = correct.iter().filter(|(a, b)| !*a && *b).count();
+    let n = b + c;
+
+    if n == 0 {
+        return McNemar {
+            b,
+            c,
+            statistic: 0.0,
+            p_value: 1.0,
+            exact_p_value: 1.0,
+        };
+    }
+
+    let statistic = ((b as f64 - c as f64).abs() - 1.0).max(0.0).powi(2) / n as f64;
+
+    // P(X <= min(b, c)) for X ~ Binomial(n, 0.5), summed in logs so large n can't underflow
+    // the terms that matter
+    let mut ln_choose = 0.0;
+    let mut tail = 0.0;
+    for k in 0..=b.min(c) {
+        if k > 0 {
+            ln_choose += ((n - k + 1) as f64).ln() - (k as f64).ln();
+        }
+        tail += (ln_choose + n as f64 * 0.5f64.ln()).exp();
+    }
+
+    McNemar {
+        b,
+        c,
+        statistic,
+        p_value: erfc((statistic / 2.0).sqrt()),
+        exact_p_value: (2.0 * tail).min(1.0),
+    }
+}
+
+/// DeLong holds DeLong's test of the difference between two correlated AUCs, e.g. of two
+/// models scored on the same rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeLong {
+    pub auc_a: f64,
+    pub auc_b: f64,
+    /// auc_a - auc_b
+    pub difference: f64,
+    /// the standard error of the difference
+    pub se: f64,
+    pub z: f64,
+    pub p_value: f64,
+}
+
+/// midranks returns the 1 based rank of each value, tied values share the mean of their ranks
+fn midranks(xs: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..xs.len()).collect();
+    order.sort_by(|a, b| xs[*a].partial_cmp(&xs[*b]).unwrap());
+
+    let mut ranks = vec![0.0; xs.len()];
+    let mut i = 0;
+
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && xs[order[j + 1]] == xs[order[i]] {
+            j += 1;
+        }
+
+        for k in i..=j {
+            ranks[order[k]] = (i + j) as f64 / 2.0 + 1.0;
+        }
+
+        i = j + 1;
+    }
+
+    ranks
+}
+
+/// structural returns the AUC of one model and its structural components, the fraction of
+/// negatives each positive outranks and of positives each negative is outranked by
+fn structural(positives: &[f64], negatives: &[f64]) -> (f64, Vec<f64>, Vec<f64>) {
+    let (m, n) = (positives.len() as f64, negatives.len() as f64);
+
+    let all: Vec<f64> = positives.iter().chain(negatives).copied().collect();
+    let tz = midranks(&all);
+    let tx = midranks(positives);
+    let ty = midranks(negatives);
+
+    let v10: Vec<f64> = (0..positives.len()).map(|i| (tz[i] - tx[i]) / n).collect();
+    let v01: Vec<f64> = (0..negatives.len())
+        .map(|j| 1.0 - (tz[positives.len() + j] - ty[j]) / m)
+        .collect();
+
+    let auc = v10.iter().sum::<f64>() / m;
+
+    (auc, v10, v01)
+}
+
+/// covariance is the sample covariance of two series of the same length
+fn covariance(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    let mx = xs.iter().sum::<f64>() / n;
+    let my = ys.iter().sum::<f64>() / n;
+
+    xs.iter()
+        .zip(ys)
+        .map(|(x, y)| (x - mx) * (y - my))
+        .sum::<f64>()
+        / (n - 1.0)
+}
+
+/// delong compares the AUCs of two models from (score a, score b, actual) rows, using the
+/// midrank algorithm of Sun and Xu. It needs at least two rows of each class, otherwise
+/// everything but the AUCs is NaN.
+pub fn delong(rows: &[(f64, f64, bool)]) -> DeLong {
+    let split = |score: fn(&(f64, f64, bool)) -> f64| -> (Vec<f64>, Vec<f64>) {
+        let positives = rows.iter().filter(|r| r.2).map(score).collect();
+        let negatives = rows.iter().filter(|r| !r.2).map(score).collect();
+        (positives, negatives)
+    };
+
+    let (pa, na) = split(|r| r.0);
+    let (pb, nb) = split(|r| r.1);
+
+    let (auc_a, v10a, v01a) = structural(&pa, &na);
+    let (auc_b, v10b, v01b) = structural(&pb, &nb);
+
+    let (m, n) = (pa.len() as f64, na.len() as f64);
+
+    // var(a - b) = var(a) + var(b) - 2 cov(a, b), with each term S10 / m + S01 / n
+    let term = |x10: &[f64], y10: &[f64], x01: &[f64], y01: &[f64]| {
+        covariance(x10, y10) / m + covariance(x01, y01) / n
+    };
+
+    let variance = term(&v10a, &v10a, &v01a, &v01a) + term(&v10b, &v10b, &v01b, &v01b)
+        - 2.0 * term(&v10a, &v10b, &v01a, &v01b);
+
+    let difference = auc_a - auc_b;
+    let se = variance.max(0.0).sqrt();
+    let z = difference / se;
+
+    DeLong {
+        auc_a,
+        auc_b,
+        difference,
+        se,
+        z,
+        // identical models have no variance and no difference
+        p_value: if se == 0.0 && difference == 0.0 {
+            1.0
+        } else {
+            erfc(z.abs() / 2f64.sqrt())
+        },
+    }
+}
+
+/// erfc is the complementary error function, from the Chebyshev fit in Numerical Recipes
+/// with a relative error below 1.2e-7
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let mut correct = vec![(true, false); 25];
+        correct.extend(vec![(false, true); 10]);
+        correct.extend(vec![(true, true); 50]);
+
+        let m = mcnemar(&correct);
+        assert_eq!((m.b, m.c), (25, 10));
+        assert!((m.statistic - 5.6).abs() < 1e-12);
+        assert!((m.p_value - 0.017961).abs() < 1e-5);
+        assert!((m.exact_p_value - 0.016674).abs() < 1e-5);
+
+        let rows = vec![
+            (0.9, 0.6, true),
+            (0.8, 0.9, true),
+            (0.7, 0.3, true),
+            (0.4, 0.4, true),
+            (0.6, 0.7, false),
+            (0.3, 0.5, false),
+            (0.2, 0.4, false),
+            (0.1, 0.2, false),
+        ];
+        let d = delong(&rows);
+
+        let auc = |f: fn(&(f64, f64, bool)) -> f64| {
+            let scores: Vec<(f64, bool)> = rows.iter().map(|r| (f(r), r.2)).collect();
+            crate::roc(&scores).auc
+        };
+        assert!((d.auc_a - auc(|r| r.0)).abs() < 1e-12);
+        assert!((d.auc_b - auc(|r| r.1)).abs() < 1e-12);
+
+        // the variance by the O(mn) definition of the structural components
+        let psi = |x: f64, y: f64| {
+            if x > y {
+                1.0
+            } else if x == y {
+                0.5
+            } else {
+                0.0
+            }
+        };
+        let component = |f: fn(&(f64, f64, bool)) -> f64| {
+            let pos: Vec<f64> = rows.iter().filter(|r| r.2).map(f).collect();
+            let neg: Vec<f64> = rows.iter().filter(|r| !r.2).map(f).collect();
+            let v10: Vec<f64> = pos
+                .iter()
+                .map(|x| neg.iter().map(|y| psi(*x, *y)).sum::<f64>() / 4.0)
+                .collect();
+            let v01: Vec<f64> = neg
+                .iter()
+                .map(|y| pos.iter().map(|x| psi(*x, *y)).sum::<f64>() / 4.0)
+                .collect();
+            (v10, v01)
+        };
+        let (a10, a01) = component(|r| r.0);
+        let (b10, b01) = component(|r| r.1);
+        let var = |x10: &[f64], y10: &[f64], x01: &[f64], y01: &[f64]| {
+            covariance(x10, y10) / 4.0 + covariance(x01, y01) / 4.0
+        };
+        let expected = var(&a10, &a10, &a01, &a01) + var(&b10, &b10, &b01, &b01)
+            - 2.0 * var(&a10, &b10, &a01, &b01);
+
+        assert!((d.se - expected.sqrt()).abs() < 1e-12);
+
+        let same: Vec<(f64, f64, bool)> = rows.iter().map(|r| (r.0, r.0, r.2)).collect();
+        assert_eq!(delong(&same).p_value, 1.0);
+    }
+}
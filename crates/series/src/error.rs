@@ -0,0 +1,117 @@
+use std::fmt;
+use std::io;
+
+/// SeriesError is returned by the parsing and statistics functions of this crate, line
+/// numbers start at 1 and count the header.
+#[derive(Debug)]
+pub enum SeriesError {
+    /// the input has no rows to compute on
+    Empty,
+    /// a cell that is not a number
+    Parse {
+        cell: String,
+        line: usize,
+    },
+    /// a missing value when the policy is to error
+    Missing {
+        line: usize,
+    },
+    /// a selected column past the end of the row
+    MissingColumn {
+        column: usize,
+        line: usize,
+    },
+    /// a row with the wrong number of columns
+    ColumnCount {
+        expected: usize,
+        found: usize,
+        line: usize,
+    },
+    /// a row with the wrong number of values, for rows not read from input
+    RowLength {
+        expected: usize,
+        found: usize,
+    },
+    /// a column selection that can't be resolved against the input
+    Columns(String),
+    /// a quoted field still open at the end of the input
+    UnterminatedQuote {
+        line: usize,
+    },
+    /// a serialized sketch that can't be read back
+    Sketch {
+        line: usize,
+    },
+    /// a serialized calibrator that can't be read back
+    Calibrator {
+        line: usize,
+    },
+    /// probabilities predicted for more than two actual classes
+    Probability {
+        classes: usize,
+    },
+    /// a group whose rows are not contiguous
+    Group {
+        group: String,
+        line: usize,
+    },
+    Io {
+        line: usize,
+        source: io::Error,
+    },
+}
+
+impl fmt::Display for SeriesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SeriesError::Empty => write!(f, "input must be a non empty set"),
+            SeriesError::Parse { cell, line } => {
+                write!(f, "error converting to float: {} at line {}", cell, line)
+            }
+            SeriesError::Missing { line } => write!(f, "missing value at line {}", line),
+            SeriesError::MissingColumn { column, line } => {
+                write!(f, "missing column {} at line {}", column, line)
+            }
+            SeriesError::ColumnCount {
+                expected,
+                found,
+                line,
+            } => write!(
+                f,
+                "expected {} columns at line {}, found {}",
+                expected, line, found
+            ),
+            SeriesError::RowLength { expected, found } => {
+                write!(f, "invalid row length {}, expected {}", found, expected)
+            }
+            SeriesError::Columns(e) => write!(f, "{}", e),
+            SeriesError::UnterminatedQuote { line } => {
+                write!(f, "unterminated quoted field starting at line {}", line)
+            }
+            SeriesError::Sketch { line } => write!(f, "invalid t-digest at line {}", line),
+            SeriesError::Calibrator { line } => write!(f, "invalid calibrator at line {}", line),
+            SeriesError::Probability { classes } => write!(
+                f,
+                "a predicted probability needs two actual classes, found {}",
+                classes
+            ),
+            SeriesError::Group { group, line } => write!(
+                f,
+                "rows of a group must be contiguous, group {} found again at line {}",
+                group, line
+            ),
+            SeriesError::Io { line, source } => {
+                write!(f, "failed to read line {}: {}", line, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SeriesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SeriesError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::SeriesError;
+
+/// Labels maps class labels, strings or numbers, to dense indices 0..N. Labels are numbered
+/// in the order they are first seen until sort is called, which orders them numerically when
+/// every label is a number, with a NaN label last, and as strings otherwise. Numbers are
+/// normalized, so "1" and "1.0" are the same label.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Labels {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+/// normalize writes a numeric label in its shortest form
+fn normalize(label: &str) -> String {
+    let label = label.trim();
+
+    match label.parse::<f64>() {
+        Ok(x) => x.to_string(),
+        Err(_) => label.to_string(),
+    }
+}
+
+impl Labels {
+    /// encode replaces numeric labels by their index in sorted order, so sparse labels such
+    /// as 1, 5 and 9 become 0, 1 and 2
+    pub fn encode(ydata: &mut [f32]) -> Labels {
+        let mut labels = Labels::default();
+
+        for y in ydata.iter_mut() {
+            *y = labels.push(&y.to_string()) as f32;
+        }
+
+        let order = labels.sort();
+        for y in ydata.iter_mut() {
+            *y = order[*y as usize] as f32;
+        }
+
+        labels
+    }
+
+    /// push returns the index of a label, adding it when it is new
+    pub fn push(&mut self, label: &str) -> usize {
+        let label = normalize(label);
+
+        if let Some(i) = self.index.get(&label) {
+            return *i;
+        }
+
+        self.names.push(label.clone());
+        self.index.insert(label, self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    /// sort orders the labels and returns the new index of each old index
+    pub fn sort(&mut self) -> Vec<usize> {
+        let numbers: Option<Vec<f64>> = self.names.iter().map(|n| n.parse().ok()).collect();
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+
+        match numbers {
+            Some(xs) => order.sort_by(|a, b| xs[*a].total_cmp(&xs[*b])),
+            None => order.sort_by(|a, b| self.names[*a].cmp(&self.names[*b])),
+        }
+
+        let mut new = vec![0; order.len()];
+        for (i, old) in order.iter().enumerate() {
+            new[*old] = i;
+        }
+
+        self.names = order.iter().map(|i| self.names[*i].clone()).collect();
+        self.index = self
+            .names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.clone(), i))
+            .collect();
+
+        new
+    }
+
+    /// get returns the index of a label, if it has been seen
+    pub fn get(&self, label: &str) -> Option<usize> {
+        self.index.get(&normalize(label)).copied()
+    }
+
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.names.get(index).map(|n| n.as_str())
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// labels are written one per line, in index order
+impl fmt::Display for Labels {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.names {
+            writeln!(f, "{}", name)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Labels {
+    type Err = SeriesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut labels = Labels::default();
+
+        for line in s.lines() {
+            labels.push(line);
+        }
+
+        if labels.is_empty() {
+            return Err(SeriesError::Empty);
+        }
+
+        Ok(labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        let mut ydata = vec![9.0, 1.0, 5.0, 1.0];
+        let labels = Labels::encode(&mut ydata);
+
+        assert_eq!(ydata, vec![2.0, 0.0, 1.0, 0.0]);
+        assert_eq!(labels.names(), ["1", "5", "9"]);
+
+        let mut labels = Labels::default();
+        assert_eq!(labels.push("virginica"), 0);
+        assert_eq!(labels.push("setosa"), 1);
+        assert_eq!(labels.push("virginica"), 0);
+        assert_eq!(labels.sort(), vec![1, 0]);
+        assert_eq!(labels.get("virginica"), Some(1));
+
+        // numbers sort as numbers, and 2.0 is 2
+        let mut labels: Labels = "10\n2.0\n".parse().unwrap();
+        labels.sort();
+        assert_eq!(labels.to_string(), "2\n10\n");
+        assert_eq!(labels.get("2"), Some(0));
+
+        // a class named nan parses as a number, and sorts after the others
+        let mut labels: Labels = "nan\n1\n-1\n".parse().unwrap();
+        labels.sort();
+        assert_eq!(labels.names(), ["-1", "1", "NaN"]);
+    }
+}
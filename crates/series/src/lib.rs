@@ -1,7 +1,38 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use std::str::FromStr;
 
+use std::io::BufRead;
+
+mod calibration;
+mod calibrator;
+mod compare;
+mod error;
+mod labels;
+mod missing;
+mod multiclass;
+mod online;
+mod optimize;
+mod quantile;
+mod reader;
+mod regression;
+mod roc;
+mod tdigest;
+pub use calibration::{calibration, calibration_multiclass, Binning, Calibration, CalibrationBin};
+pub use calibrator::{Calibrator, Method};
+pub use compare::{delong, mcnemar, DeLong, McNemar};
+pub use error::SeriesError;
+pub use labels::Labels;
+pub use missing::{impute, impute_vector, is_missing, Missing, MissingCounts};
+pub use multiclass::{multiclass, Average, ClassReport, Multiclass};
+pub use online::{OnlineCorrelation, OnlineStats};
+pub use optimize::{optimize, Cost, Objective, Optimum};
+pub use quantile::{k_quantiles, quantile, quantiles, QuantileType};
+pub use reader::{complete_rows, Delimiter, Records, Rows};
+pub use regression::{regression, Regression};
+pub use roc::{roc, Roc, RocPoint};
+pub use tdigest::TDigest;
+
 #[derive(Default)]
 pub struct Series {
     pub data: Vec<f64>,
@@ -11,6 +42,8 @@ pub struct Series {
     pub var: f64,
     pub min: f64,
     pub max: f64,
+    pub skewness: f64,
+    pub kurtosis: f64,
 }
 
 impl Series {
@@ -26,238 +59,651 @@ impl Series {
             return;
         }
 
-        self.median = if self.data.len() > 1 {
-            let mut temp = self.data.clone();
-            temp.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            let mid = self.data.len() as f64 / 2.0;
-            self.data[mid as usize]
-        } else {
-            0.0
-        };
+        let mut sorted = self.data.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.set(&summarize(&sorted, &[]));
+    }
+
+    /// summary computes the stats and the given percentiles, each a probability in [0, 1]
+    pub fn summary(&mut self, percentiles: &[f64]) -> Result<Summary, SeriesError> {
+        if self.data.is_empty() {
+            return Err(SeriesError::Empty);
+        }
 
-        self.min = self.data[0];
-        self.max = self.data[0];
+        let mut sorted = self.data.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-        for x in &self.data {
-            if *x > self.max {
-                self.max = *x;
-                continue;
+        let summary = summarize(&sorted, percentiles);
+        self.set(&summary);
+
+        Ok(summary)
+    }
+
+    fn set(&mut self, summary: &Summary) {
+        self.min = summary.min;
+        self.max = summary.max;
+        self.mean = summary.mean;
+        self.median = summary.median;
+        self.var = summary.var;
+        self.stdev = summary.stdev;
+        self.skewness = summary.skewness;
+        self.kurtosis = summary.kurtosis;
+    }
+}
+
+/// summarize computes the summary of non empty sorted values, the median and percentiles are
+/// read straight from them
+fn summarize(sorted: &[f64], percentiles: &[f64]) -> Summary {
+    let mut online = OnlineStats::default();
+    for x in sorted {
+        online.push(*x);
+    }
+
+    let typ = QuantileType::default();
+
+    Summary {
+        n: sorted.len(),
+        missing: 0,
+        min: online.min,
+        max: online.max,
+        mean: online.mean,
+        median: quantile(sorted, 0.5, typ),
+        stdev: online.stdev(),
+        var: online.var(),
+        skewness: online.skewness(),
+        kurtosis: online.kurtosis(),
+        percentiles: percentiles
+            .iter()
+            .map(|p| (*p, quantile(sorted, *p, typ)))
+            .collect(),
+    }
+}
+
+/// Summary holds the summary statistics of a single vector, formatting is left to the caller.
+/// The percentiles are (probability, value) pairs.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub n: usize,
+    pub missing: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub stdev: f64,
+    pub var: f64,
+    pub skewness: f64,
+    pub kurtosis: f64,
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl Summary {
+    /// nan is the summary of a column without values
+    fn nan(percentiles: &[f64]) -> Self {
+        Summary {
+            min: f64::NAN,
+            max: f64::NAN,
+            mean: f64::NAN,
+            median: f64::NAN,
+            stdev: f64::NAN,
+            var: f64::NAN,
+            skewness: f64::NAN,
+            kurtosis: f64::NAN,
+            percentiles: percentiles.iter().map(|p| (*p, f64::NAN)).collect(),
+            ..Summary::default()
+        }
+    }
+}
+
+/// describe summarizes every column of a matrix, like pandas' describe, see Describe. The
+/// matrix is taken by value so each row is freed as it is summarized.
+pub fn describe(
+    matrix: Matrix,
+    missing: Missing,
+    percentiles: &[f64],
+) -> Result<Vec<Summary>, SeriesError> {
+    let cols = matrix.header.len();
+    let mut describe = Describe::new(missing, usize::MAX, 100.0);
+
+    for row in matrix.xdata {
+        if row.len() != cols {
+            return Err(SeriesError::RowLength {
+                expected: cols,
+                found: row.len(),
+            });
+        }
+
+        describe.push(row)?;
+    }
+
+    describe.summaries(percentiles)
+}
+
+/// Describe summarizes every column of rows read one at a time. Missing values are expected
+/// as NaN and counted per column before the policy is applied: skip-row drops every row with
+/// a missing value, skip-cell and nan leave the missing values out of their column only, and
+/// the impute policies fill them in once the rows are exhausted. A column with no values has
+/// an n of 0 and NaN stats.
+///
+/// The moments are accumulated online in constant memory. The values themselves are kept for
+/// an exact median and percentiles until there are more than limit of them, then each column
+/// moves them into a t-digest and the quantiles are estimated from there on. A limit of 0
+/// always estimates.
+pub struct Describe {
+    missing: Missing,
+    limit: usize,
+    compression: f64,
+    stats: Vec<OnlineStats>,
+    counts: Vec<usize>,
+    values: Vec<Vec<f64>>,
+    digests: Vec<TDigest>,
+    kept: usize,
+}
+
+impl Describe {
+    pub fn new(missing: Missing, limit: usize, compression: f64) -> Self {
+        Describe {
+            missing,
+            limit,
+            compression,
+            stats: vec![],
+            counts: vec![],
+            values: vec![],
+            digests: vec![],
+            kept: 0,
+        }
+    }
+
+    /// estimated is true once the quantiles are estimated rather than exact
+    pub fn estimated(&self) -> bool {
+        !self.digests.is_empty()
+    }
+
+    pub fn push(&mut self, mut row: Vec<f64>) -> Result<(), SeriesError> {
+        if self.stats.is_empty() {
+            self.stats = vec![OnlineStats::default(); row.len()];
+            self.counts = vec![0; row.len()];
+            self.values = vec![vec![]; row.len()];
+        }
+
+        if row.len() != self.stats.len() {
+            return Err(SeriesError::RowLength {
+                expected: self.stats.len(),
+                found: row.len(),
+            });
+        }
+
+        for (count, x) in self.counts.iter_mut().zip(&row) {
+            *count += x.is_nan() as usize;
+        }
+
+        match self.missing {
+            Missing::SkipRow if row.iter().any(|x| x.is_nan()) => return Ok(()),
+            Missing::ImputeConst(c) => {
+                for x in row.iter_mut().filter(|x| x.is_nan()) {
+                    *x = c;
+                }
             }
+            _ => (),
+        }
 
-            if *x < self.min {
-                self.min = *x;
+        if !self.estimated() && self.kept + row.len() > self.limit {
+            self.digests = vec![TDigest::new(self.compression); row.len()];
+
+            for (digest, values) in self.digests.iter_mut().zip(&mut self.values) {
+                for x in values.drain(..) {
+                    digest.push(x);
+                }
+                values.shrink_to_fit();
             }
         }
 
-        self.mean = self.data.iter().sum::<f64>() as f64 / self.data.len() as f64;
+        for (i, x) in row.into_iter().enumerate().filter(|(_, x)| !x.is_nan()) {
+            self.stats[i].push(x);
 
-        let sum: f64 = self
-            .data
-            .iter()
-            .map(|x| (*x - self.mean).powf(2.0))
-            .collect::<Vec<f64>>()
-            .iter()
-            .sum();
+            if self.estimated() {
+                self.digests[i].push(x);
+            } else {
+                self.values[i].push(x);
+                self.kept += 1;
+            }
+        }
 
-        self.var = sum / self.data.len() as f64;
-        self.stdev = (sum / self.data.len() as f64).sqrt();
+        Ok(())
     }
 
-    pub fn summary(mut self) {
-        self.stats();
+    /// summaries imputes the missing values, if the policy does, and summarizes each column
+    pub fn summaries(self, percentiles: &[f64]) -> Result<Vec<Summary>, SeriesError> {
+        if self.stats.is_empty() {
+            return Err(SeriesError::Empty);
+        }
+
+        let mut digests = self.digests.into_iter();
+        let mut out = vec![];
 
-        println!(
-            "{:<11}{:<11}{:<11}{:<11}{:<11}{:<11}{:<11}",
-            "n", "min", "max", "mean", "median", "sd", "var"
-        );
-        println!(
-            "{:<11}{:<11.4}{:<11.4}{:<11.4}{:<11.4}{:<11.4}{:<11.4}",
-            self.data.len(),
-            self.min,
-            self.max,
-            self.mean,
-            self.median,
-            self.stdev,
-            self.var
-        );
-    }
+        for ((mut stats, mut values), count) in
+            self.stats.into_iter().zip(self.values).zip(self.counts)
+        {
+            let mut digest = digests.next();
+
+            if stats.n == 0 {
+                out.push(Summary {
+                    missing: count,
+                    ..Summary::nan(percentiles)
+                });
+                continue;
+            }
+
+            let typ = QuantileType::default();
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            let fill = match self.missing {
+                Missing::ImputeMean => Some(stats.mean),
+                Missing::ImputeMedian => match &mut digest {
+                    Some(digest) => Some(digest.quantile(0.5)),
+                    None => Some(quantile(&values, 0.5, typ)),
+                },
+                _ => None,
+            };
+
+            if let Some(fill) = fill {
+                for _ in 0..count {
+                    stats.push(fill);
+                }
+
+                match &mut digest {
+                    Some(digest) => (0..count).for_each(|_| digest.push(fill)),
+                    None => {
+                        let at = values.partition_point(|x| *x < fill);
+                        values.splice(at..at, std::iter::repeat_n(fill, count));
+                    }
+                }
+            }
 
-    pub fn summary_t(mut self) {
-        self.stats();
+            let mut estimate = |p: f64| match &mut digest {
+                Some(digest) => digest.quantile(p),
+                None => quantile(&values, p, typ),
+            };
+
+            out.push(Summary {
+                n: stats.n,
+                missing: count,
+                min: stats.min,
+                max: stats.max,
+                mean: stats.mean,
+                median: estimate(0.5),
+                stdev: stats.stdev(),
+                var: stats.var(),
+                skewness: stats.skewness(),
+                kurtosis: stats.kurtosis(),
+                percentiles: percentiles.iter().map(|p| (*p, estimate(*p))).collect(),
+            });
+        }
 
-        println!("{:<8}{:<8}", "N", self.data.len());
-        println!("{:<8}{:<8.4}", "min", self.min);
-        println!("{:<8}{:<8.4}", "max", self.max);
-        println!("{:<8}{:<8.4}", "mean", self.mean);
-        println!("{:<8}{:<8.4}", "med", self.median);
-        println!("{:<8}{:<8.4}", "stdev", self.stdev);
-        println!("{:<8}{:<8.4}", "var", self.var);
+        Ok(out)
     }
 }
 
-pub fn confusion_matrix(tuples: &[(f32, f32)], threshold: Option<f32>) -> Vec<Vec<u32>> {
-    let mut classes = HashMap::new();
+/// ConfusionCounts accumulates a confusion matrix one prediction at a time. The matrix
+/// grows as new classes are seen, so the input only needs to be read once. Classes are
+/// labels, numbers or strings, encoded with Labels so they need not be 0..N.
+///
+/// A predicted number with a fraction is a probability of the positive class. It is
+/// thresholded and counted as the second of the two actual classes in sorted order, e.g.
+/// "yes" of "no" and "yes", or as 0 or 1 when the actual classes are 0 and 1.
+#[derive(Default, Debug, Clone)]
+pub struct ConfusionCounts {
+    threshold: Option<f32>,
+    labels: Labels,
+    // counts[predicted][actual], by label index in the order first seen
+    counts: Vec<Vec<u32>>,
+    // scored[actual] counts the probabilities [below, at or above] the threshold
+    scored: Vec<[u32; 2]>,
+    actual: Vec<bool>,
+}
+
+impl ConfusionCounts {
+    pub fn new(threshold: Option<f32>) -> Self {
+        ConfusionCounts {
+            threshold,
+            ..ConfusionCounts::default()
+        }
+    }
 
-    for (_, c) in tuples {
-        // f32 is not hashable, convert to string
-        classes.insert(format!("{}", c), 1);
+    pub fn push(&mut self, p: f32, actual: f32) -> Result<(), SeriesError> {
+        self.push_labels(&p.to_string(), &actual.to_string())?;
+        Ok(())
     }
 
-    let size = classes.keys().len();
-    let mut matrix = vec![];
+    /// push_labels counts a predicted and actual label and returns their indices, see order.
+    /// A probability, a predicted number with a fraction, is positive at 0.5 or at the
+    /// threshold. It is an error once there are probabilities and more than two actual
+    /// classes.
+    pub fn push_labels(
+        &mut self,
+        predicted: &str,
+        actual: &str,
+    ) -> Result<(usize, usize), SeriesError> {
+        let actual = self.labels.push(actual);
+        self.grow();
+        self.actual[actual] = true;
+
+        // the indices of probabilities are 0 and 1, those of labels follow
+        let predicted = match predicted.trim().parse::<f32>() {
+            Ok(p) if p.fract() != 0.0 => {
+                let positive = p >= self.threshold.unwrap_or(0.5);
+                self.scored[actual][positive as usize] += 1;
+                positive as usize
+            }
+            _ => {
+                let predicted = self.labels.push(predicted);
+                self.grow();
+                self.counts[predicted][actual] += 1;
+                predicted + 2
+            }
+        };
+
+        let classes = self.actual.iter().filter(|a| **a).count();
+        if classes > 2 && self.scored.iter().any(|s| s[0] + s[1] > 0) {
+            return Err(SeriesError::Probability { classes });
+        }
+
+        Ok((predicted, actual + 2))
+    }
 
-    // create the confusion matrix
-    for _ in 0..size {
-        matrix.push(vec![0; size]);
+    fn grow(&mut self) {
+        let size = self.labels.len();
+        if size > self.counts.len() {
+            for row in self.counts.iter_mut() {
+                row.resize(size, 0);
+            }
+            self.counts.resize(size, vec![0; size]);
+            self.scored.resize(size, [0, 0]);
+            self.actual.resize(size, false);
+        }
     }
 
-    // intended to use only with binary (0,1) ranges. Not softprob (yet).
-    for (p, actual_class_col) in tuples {
-        let predicted_class_row = if let Some(t) = threshold {
-            (*p + (1.0 - t)) as usize
-        } else if size == 2 {
-            (*p + 0.5) as usize
+    /// resolve counts the probabilities as their classes and sorts the classes. It returns
+    /// the sorted labels, counts[predicted][actual] by sorted index and the sorted index of
+    /// each index returned by push_labels.
+    fn resolve(&self) -> (Labels, Vec<Vec<u32>>, Vec<usize>) {
+        let mut labels = self.labels.clone();
+        let scored = self.scored.iter().any(|s| s[0] + s[1] > 0);
+        let actual: Vec<usize> = (0..self.labels.len()).filter(|i| self.actual[*i]).collect();
+        let binary = actual
+            .iter()
+            .all(|i| matches!(self.labels.names()[*i].as_str(), "0" | "1"));
+
+        let classes = if !scored {
+            None
+        } else if !binary && actual.len() == 2 {
+            let rank = self.labels.clone().sort();
+            let mut pair = actual.clone();
+            pair.sort_by_key(|i| rank[*i]);
+            Some([pair[0], pair[1]])
         } else {
-            *p as usize
+            Some([labels.push("0"), labels.push("1")])
+        };
+
+        let size = labels.len();
+        let mut counts = vec![vec![0; size]; size];
+        for (i, row) in self.counts.iter().enumerate() {
+            for (j, count) in row.iter().enumerate() {
+                counts[i][j] += count;
+            }
+        }
+        if let Some(classes) = classes {
+            for (j, scored) in self.scored.iter().enumerate() {
+                counts[classes[0]][j] += scored[0];
+                counts[classes[1]][j] += scored[1];
+            }
+        }
+
+        let order = labels.sort();
+        let mut sorted = vec![vec![0; size]; size];
+        for (i, row) in counts.iter().enumerate() {
+            for (j, count) in row.iter().enumerate() {
+                sorted[order[i]][order[j]] = *count;
+            }
+        }
+
+        let mut index = match classes {
+            Some(classes) => vec![order[classes[0]], order[classes[1]]],
+            None => vec![0, 0],
         };
+        index.extend(&order[..self.labels.len()]);
 
-        matrix[predicted_class_row][*actual_class_col as usize] += 1;
+        (labels, sorted, index)
     }
 
-    // reverse each row so they are in descending order
-    // after this the matrix is in descending order from top/left to bottom/right
-    // the purpose for ordering this way is for a binary prediction this defautt layout
-    // matches a confusion matrix
-    // TP FP
-    // FN TN
-    for i in 0..size {
-        matrix[i].reverse();
+    /// order maps the indices returned by push_labels to classes in sorted order
+    pub fn order(&self) -> Vec<usize> {
+        self.resolve().2
+    }
+
+    /// labels returns the classes in sorted order, the index of each is its class in the
+    /// stats of confusion_matrix_stats
+    pub fn labels(&self) -> Labels {
+        self.resolve().0
+    }
+
+    /// matrix returns the counts in descending class order from top/left to bottom/right,
+    /// predicted on the y-axis and actual on the x-axis. For a binary prediction this
+    /// layout matches the usual confusion matrix
+    /// TP FP
+    /// FN TN
+    pub fn matrix(&self) -> Vec<Vec<u32>> {
+        let (_, counts, _) = self.resolve();
+        let size = counts.len();
+        let mut matrix = vec![vec![0; size]; size];
+
+        for (i, row) in counts.iter().enumerate() {
+            for (j, count) in row.iter().enumerate() {
+                matrix[size - 1 - i][size - 1 - j] = *count;
+            }
+        }
+
+        matrix
+    }
+}
+
+pub fn confusion_matrix(
+    tuples: &[(f32, f32)],
+    threshold: Option<f32>,
+) -> Result<Vec<Vec<u32>>, SeriesError> {
+    let mut counts = ConfusionCounts::new(threshold);
+
+    for (p, actual) in tuples {
+        counts.push(*p, *actual)?;
     }
-    matrix.reverse();
 
-    matrix
+    Ok(counts.matrix())
 }
 
+/// CMatrixStats holds the one-vs-rest stats of a class of a confusion matrix. Ratios with a
+/// zero denominator, e.g. the precision of a class that is never predicted, are NaN.
 pub struct CMatrixStats {
+    /// the index of the class in sorted order, as in ConfusionCounts::labels
     pub label: usize,
+    /// the number of rows of the class
+    pub support: u32,
     pub fpr: f32,
     pub tpr: f32,
     pub fnr: f32,
     pub tnr: f32,
+    pub precision: f32,
+    /// the negative predictive value, tn / (tn + fn)
+    pub npv: f32,
+    pub f1: f32,
+    pub mcc: f32,
+    pub kappa: f32,
+    /// (tpr + tnr) / 2
+    pub balanced_accuracy: f32,
 }
 
+/// confusion_matrix_stats computes the stats of each class of a matrix in the layout of
+/// ConfusionCounts::matrix, in sorted class order
 pub fn confusion_matrix_stats(matrix: &[Vec<u32>]) -> Vec<CMatrixStats> {
     let size = matrix.len();
+    let total: f32 = matrix.iter().flatten().map(|c| *c as f32).sum();
 
-    let mut counts = HashMap::new();
-    for i in 0..size {
-        // TP FN FP TN
-        counts.insert(i, vec![0.0, 0.0, 0.0, 0.0]);
-    }
-
-    let mut total = 0.0;
+    let mut stats = vec![];
 
     for i in 0..size {
-        let mut fn_i = 0.0;
-
-        for j in 0..size {
-            // total the entire matrix
-            total += matrix[i][j] as f32;
-
-            // TP
-            if i == j {
-                let data = counts.get_mut(&i).unwrap();
-                data[0] = matrix[i][j] as f32;
-                continue;
-            }
-
-            let data = counts.get_mut(&i).unwrap();
-            // FP
-            data[2] += matrix[j][i] as f32;
-
-            // FN
-            fn_i += matrix[i][j] as f32;
-        }
+        // row i is the predicted class and column i the actual class
+        let tp = matrix[i][i] as f32;
+        let fp = matrix[i].iter().map(|c| *c as f32).sum::<f32>() - tp;
+        let fn_ = matrix.iter().map(|row| row[i] as f32).sum::<f32>() - tp;
+        let tn = total - tp - fp - fn_;
 
-        let data = counts.get_mut(&i).unwrap();
-        // FN
-        data[1] = fn_i;
-    }
+        let tpr = tp / (tp + fn_);
+        let tnr = tn / (tn + fp);
+        let precision = tp / (tp + fp);
 
-    let mut stats = vec![];
-
-    for k in 0..size {
-        let v = counts.get_mut(&k).unwrap();
-        // TN
-        v[3] = total - v[0] - v[1] - v[2];
+        // the agreement expected by chance, from the predicted and actual totals
+        let expected = ((tp + fp) * (tp + fn_) + (tn + fn_) * (tn + fp)) / (total * total);
+        let observed = (tp + tn) / total;
 
         stats.push(CMatrixStats {
-            label: k,
-            fpr: v[2] / (v[2] + v[3]),
-            tpr: v[0] / (v[0] + v[1]),
-            fnr: v[1] / (v[1] + v[0]),
-            tnr: v[3] / (v[3] + v[1]),
+            label: size - 1 - i,
+            support: (tp + fn_) as u32,
+            fpr: fp / (fp + tn),
+            tpr,
+            fnr: fn_ / (fn_ + tp),
+            tnr,
+            precision,
+            npv: tn / (tn + fn_),
+            f1: 2.0 * precision * tpr / (precision + tpr),
+            mcc: (tp * tn - fp * fn_) / ((tp + fp) * (tp + fn_) * (tn + fp) * (tn + fn_)).sqrt(),
+            kappa: (observed - expected) / (1.0 - expected),
+            balanced_accuracy: (tpr + tnr) / 2.0,
         })
     }
 
-    stats.sort_by(|a, b| a.label.cmp(&b.label));
+    stats.sort_by_key(|s| s.label);
 
     stats
 }
 
-pub fn threshold_table_stats(tuples: &[(f32, f32)]) -> Vec<Vec<f32>> {
-    let mut out = vec![];
-    let mut t = 0.05;
+/// CMatrixSummary holds the stats of a whole confusion matrix. For more than two classes
+/// the MCC is Gorodkin's generalization and the balanced accuracy is the mean tpr of the
+/// classes that occur.
+pub struct CMatrixSummary {
+    pub n: u32,
+    pub accuracy: f32,
+    pub balanced_accuracy: f32,
+    pub kappa: f32,
+    pub mcc: f32,
+}
 
-    loop {
-        if t > 1.0 {
-            break;
-        }
+pub fn confusion_matrix_summary(matrix: &[Vec<u32>]) -> CMatrixSummary {
+    let size = matrix.len();
+    let n: f32 = matrix.iter().flatten().map(|c| *c as f32).sum();
+    let correct: f32 = (0..size).map(|i| matrix[i][i] as f32).sum();
+
+    // the predicted and actual totals of each class
+    let predicted: Vec<f32> = matrix
+        .iter()
+        .map(|row| row.iter().map(|c| *c as f32).sum())
+        .collect();
+    let actual: Vec<f32> = (0..size)
+        .map(|j| matrix.iter().map(|row| row[j] as f32).sum())
+        .collect();
+
+    let chance: f32 = predicted.iter().zip(&actual).map(|(p, a)| p * a).sum();
+    let squares = |xs: &[f32]| xs.iter().map(|x| x * x).sum::<f32>();
+
+    let recalls: Vec<f32> = (0..size)
+        .filter(|i| actual[*i] > 0.0)
+        .map(|i| matrix[i][i] as f32 / actual[i])
+        .collect();
+
+    CMatrixSummary {
+        n: n as u32,
+        accuracy: correct / n,
+        balanced_accuracy: recalls.iter().sum::<f32>() / recalls.len() as f32,
+        kappa: (correct / n - chance / (n * n)) / (1.0 - chance / (n * n)),
+        mcc: (correct * n - chance)
+            / ((n * n - squares(&predicted)) * (n * n - squares(&actual))).sqrt(),
+    }
+}
 
-        let mut ttp: f32 = 0.0;
-        let mut tfp: f32 = 0.0;
-        let mut tfn: f32 = 0.0;
-        let mut ttn: f32 = 0.0;
+/// ThresholdCounts accumulates the TP, FP, FN and TN counts of a binary prediction at the
+/// thresholds 0.05 to 0.95, 0.05 apart.
+#[derive(Debug, Clone)]
+pub struct ThresholdCounts {
+    thresholds: Vec<f32>,
+    // TP FP FN TN for each threshold
+    counts: Vec<[f32; 4]>,
+}
 
-        for (p, a) in tuples {
-            if *p >= t && *a == 1.0 {
-                ttp += 1.0;
-                continue;
-            }
+impl Default for ThresholdCounts {
+    fn default() -> Self {
+        // dividing rounds each threshold once, adding 0.05 over and over drifts to 0.35000002
+        let thresholds: Vec<f32> = (1..20).map(|i| i as f32 / 20.0).collect();
 
-            if *p >= t && *a == 0.0 {
-                tfp += 1.0;
-                continue;
-            }
+        ThresholdCounts {
+            counts: vec![[0.0; 4]; thresholds.len()],
+            thresholds,
+        }
+    }
+}
 
-            if *p < t && *a == 1.0 {
-                tfn += 1.0;
+impl ThresholdCounts {
+    pub fn push(&mut self, p: f32, a: f32) {
+        for (t, counts) in self.thresholds.iter().zip(self.counts.iter_mut()) {
+            let index = if a == 1.0 {
+                if p >= *t {
+                    0
+                } else {
+                    2
+                }
+            } else if a == 0.0 {
+                if p >= *t {
+                    1
+                } else {
+                    3
+                }
+            } else {
                 continue;
-            }
+            };
 
-            if *p < t && *a == 0.0 {
-                ttn += 1.0;
-                continue;
-            }
+            counts[index] += 1.0;
+        }
+    }
+
+    /// stats returns a row of [t, precision, tpr, f1, fpr] for each threshold
+    pub fn stats(&self) -> Vec<Vec<f32>> {
+        let mut out = vec![];
+
+        for (t, [ttp, tfp, tfn, ttn]) in self.thresholds.iter().zip(&self.counts) {
+            let precision = ttp / (ttp + tfp);
+            let tpr = ttp / (ttp + tfn);
+            let f1 = 2.0 * (tpr * precision) / (tpr + precision);
+            let fpr = tfp / (tfp + ttn);
+
+            out.push(vec![*t, precision, tpr, f1, fpr]);
         }
 
-        let precision = ttp / (ttp + tfp);
-        let tpr = ttp / (ttp + tfn);
-        let f1 = 2.0 * (tpr * precision) / (tpr + precision);
-        let fpr = tfp / (tfp + ttn);
+        out
+    }
+}
 
-        out.push(vec![t, precision, tpr, f1, fpr]);
+pub fn threshold_table_stats(tuples: &[(f32, f32)]) -> Vec<Vec<f32>> {
+    let mut counts = ThresholdCounts::default();
 
-        t += 0.05;
+    for (p, a) in tuples {
+        counts.push(*p, *a);
     }
 
-    out
+    counts.stats()
 }
 
-pub fn correlation_matrix(input: &[Vec<f64>]) -> Vec<Vec<f64>> {
+/// correlation_matrix computes the Pearson correlation matrix of a matrix held in memory,
+/// see OnlineCorrelation to compute it one row at a time.
+#[allow(clippy::needless_range_loop)]
+pub fn correlation_matrix(input: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, SeriesError> {
     if input.is_empty() {
-        eprintln!("input must be a non empty set");
-        std::process::exit(1);
+        return Err(SeriesError::Empty);
     }
 
     let rows = input.len();
@@ -311,12 +757,52 @@ pub fn correlation_matrix(input: &[Vec<f64>]) -> Vec<Vec<f64>> {
         }
     }
 
-    out
+    Ok(out)
+}
+
+/// histogram splits the range [min, max] of the input into k equal width bins and
+/// counts the values falling into each. Each bin is returned as (lower, upper, count),
+/// the last bin is closed on both ends so max is always counted.
+pub fn histogram(input: &[f64], k: usize) -> Vec<(f64, f64, usize)> {
+    if input.is_empty() || k == 0 {
+        return vec![];
+    }
+
+    let mut min = input[0];
+    let mut max = input[0];
+    for x in input {
+        if *x < min {
+            min = *x;
+        }
+
+        if *x > max {
+            max = *x;
+        }
+    }
+
+    let width = (max - min) / k as f64;
+    let mut counts = vec![0; k];
+
+    for x in input {
+        let index = if width == 0.0 {
+            0
+        } else {
+            ((*x - min) / width) as usize
+        };
+
+        counts[index.min(k - 1)] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, c)| (min + i as f64 * width, min + (i + 1) as f64 * width, c))
+        .collect()
 }
 
 pub fn str_to_vector<F: FromStr>(s: &str, sep: &str) -> Result<Vec<F>, <F as FromStr>::Err> {
     let mut out = vec![];
-    for i in s.split(sep).into_iter() {
+    for i in s.split(sep) {
         match i.trim().parse() {
             Ok(f) => {
                 out.push(f);
@@ -331,109 +817,436 @@ pub fn str_to_vector<F: FromStr>(s: &str, sep: &str) -> Result<Vec<F>, <F as Fro
     Ok(out)
 }
 
-pub fn to_tuple(input: &str) -> Vec<(f32, f32)> {
-    let mut data = vec![];
+/// Column is a single item of a column selection, either an index, an inclusive range of
+/// indices, or a header name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Index(usize),
+    Range(usize, Option<usize>),
+    Name(String),
+}
 
-    for line in input.split('\n') {
-        if line == "\n" || line.is_empty() {
-            continue;
-        }
+/// Columns is a comma separated column selection, e.g. "0", "1-3", "2-", or
+/// "sepal_length,petal_length". Names require a header to resolve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Columns(pub Vec<Column>);
 
-        let cols = line.split(',').collect::<Vec<&str>>();
-        if cols.len() != 2 {
-            eprintln!("warning: invalid column count for parse_tuple: {}", line);
-            continue;
-        }
+impl FromStr for Columns {
+    type Err = String;
 
-        let temp: Result<f32, _> = cols[0].trim().parse();
-        let v1 = match temp {
-            Ok(f) => f,
-            Err(e) => {
-                eprintln!("{}", e);
-                std::process::exit(1);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut out = vec![];
+
+        for item in s.split(',') {
+            let item = item.trim();
+            if item.is_empty() {
+                return Err(format!("invalid column selection: {}", s));
             }
-        };
 
-        let temp: Result<f32, _> = cols[1].trim().parse();
-        let v2 = match temp {
-            Ok(f) => f,
-            Err(e) => {
-                eprintln!("{}", e);
-                std::process::exit(1);
+            if let Ok(i) = item.parse() {
+                out.push(Column::Index(i));
+                continue;
             }
-        };
 
-        data.push((v1, v2));
+            // a range is only a range if both ends are indices, otherwise treat it as a name
+            if let Some((start, end)) = item.split_once('-') {
+                if let Ok(start) = start.trim().parse() {
+                    if end.trim().is_empty() {
+                        out.push(Column::Range(start, None));
+                        continue;
+                    }
+
+                    if let Ok(end) = end.trim().parse() {
+                        if end < start {
+                            return Err(format!("invalid column range: {}", item));
+                        }
+
+                        out.push(Column::Range(start, Some(end)));
+                        continue;
+                    }
+                }
+            }
+
+            out.push(Column::Name(item.to_string()));
+        }
+
+        Ok(Columns(out))
+    }
+}
+
+impl Columns {
+    /// resolve converts the selection into column indices for a row of 'size' columns. Names
+    /// are looked up in the header, if there is one.
+    pub fn resolve(&self, header: Option<&[String]>, size: usize) -> Result<Vec<usize>, String> {
+        let mut out = vec![];
+
+        for column in &self.0 {
+            match column {
+                Column::Index(i) => {
+                    if *i >= size {
+                        return Err(format!("column {} out of range, input has {}", i, size));
+                    }
+                    out.push(*i);
+                }
+
+                Column::Range(start, end) => {
+                    let end = end.unwrap_or(size.saturating_sub(1));
+                    if end >= size {
+                        return Err(format!("column {} out of range, input has {}", end, size));
+                    }
+                    out.extend(*start..=end);
+                }
+
+                Column::Name(name) => {
+                    let header = match header {
+                        Some(h) => h,
+                        None => {
+                            return Err(format!("column name {} requires a header", name));
+                        }
+                    };
+
+                    match header.iter().position(|h| h == name) {
+                        Some(i) => out.push(i),
+                        None => return Err(format!("column {} not found in header", name)),
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// select resolves an optional column selection, with no selection every column is used
+pub(crate) fn select(
+    columns: Option<&Columns>,
+    header: Option<&[String]>,
+    size: usize,
+) -> Result<Vec<usize>, SeriesError> {
+    match columns {
+        Some(c) => c.resolve(header, size).map_err(SeriesError::Columns),
+        None => Ok((0..size).collect()),
+    }
+}
+
+/// parse_value parses a cell that may be missing. Missing values are NaN, or an error when
+/// that is the policy.
+pub(crate) fn parse_value(cell: &str, line: usize, missing: Missing) -> Result<f64, SeriesError> {
+    if !is_missing(cell) {
+        return cell.trim().parse().map_err(|_| SeriesError::Parse {
+            cell: cell.to_string(),
+            line,
+        });
+    }
+
+    if missing == Missing::Error {
+        return Err(SeriesError::Missing { line });
     }
 
-    data
+    Ok(f64::NAN)
 }
 
-pub fn to_vector(raw_inputs: &str, with_header: bool) -> Vec<f64> {
+/// to_tuple parses a list of 'predicted, actual' pairs. If columns is given, it must select
+/// exactly two columns, the first is used as predicted and the second as actual. Pairs
+/// with a missing value are left out unless the policy imputes them.
+pub fn to_tuple<R: BufRead>(
+    input: R,
+    with_header: bool,
+    columns: Option<&Columns>,
+    delimiter: Delimiter,
+    missing: Missing,
+) -> Result<(Vec<(f32, f32)>, MissingCounts), SeriesError> {
     let mut data = vec![];
+    let mut rows = Rows::new(input, delimiter, with_header, columns.cloned())?.missing(missing);
 
-    for (index, line) in raw_inputs.split('\n').enumerate() {
-        if index == 0 && with_header {
-            continue;
+    while let Some(row) = rows.next() {
+        let row = row?;
+
+        if row.len() != 2 {
+            return Err(SeriesError::ColumnCount {
+                expected: 2,
+                found: row.len(),
+                line: rows.line(),
+            });
         }
 
-        if line == "\n" || line.is_empty() {
-            continue;
+        data.push(row);
+    }
+
+    impute(&mut data, missing);
+
+    let tuples = data
+        .iter()
+        .filter(|row| !row[0].is_nan() && !row[1].is_nan())
+        .map(|row| (row[0] as f32, row[1] as f32))
+        .collect();
+
+    Ok((tuples, rows.missing_counts().clone()))
+}
+
+/// to_vector parses a single vector. If column is given, that column is selected out of each
+/// line, otherwise each line is expected to hold a single value.
+pub fn to_vector<R: BufRead>(
+    input: R,
+    with_header: bool,
+    column: Option<&Columns>,
+    delimiter: Delimiter,
+    missing: Missing,
+) -> Result<(Vec<f64>, MissingCounts), SeriesError> {
+    let mut data = vec![];
+    let mut rows = Rows::new(input, delimiter, with_header, column.cloned())?.missing(missing);
+
+    while let Some(row) = rows.next() {
+        let row = row?;
+
+        if row.len() != 1 {
+            return Err(SeriesError::ColumnCount {
+                expected: 1,
+                found: row.len(),
+                line: rows.line(),
+            });
         }
 
-        let temp: Result<f64, _> = line.trim().parse();
-        match temp {
-            Ok(f) => data.push(f),
-            Err(_) => {
-                eprintln!("error converting to float: {} at line {}", line, index);
-                std::process::exit(1);
-            }
+        data.push(row[0]);
+    }
+
+    // a single vector has no use for NaN placeholders
+    if missing == Missing::SkipCell || missing == Missing::Nan {
+        data.retain(|x| !x.is_nan());
+    }
+
+    impute_vector(&mut data, missing);
+
+    Ok((data, rows.missing_counts().clone()))
+}
+
+/// to_tdigest is to_vector for inputs too large to hold in memory, the values are read into a
+/// t-digest with the given compression. Imputed values are added at the end, at the mean or
+/// at the estimated median of the values read.
+pub fn to_tdigest<R: BufRead>(
+    input: R,
+    with_header: bool,
+    column: Option<&Columns>,
+    delimiter: Delimiter,
+    missing: Missing,
+    compression: f64,
+) -> Result<(TDigest, MissingCounts), SeriesError> {
+    let mut digest = TDigest::new(compression);
+    let mut stats = OnlineStats::default();
+    let mut nan = 0;
+    let mut rows = Rows::new(input, delimiter, with_header, column.cloned())?.missing(missing);
+
+    while let Some(row) = rows.next() {
+        let row = row?;
+
+        if row.len() != 1 {
+            return Err(SeriesError::ColumnCount {
+                expected: 1,
+                found: row.len(),
+                line: rows.line(),
+            });
+        }
+
+        if row[0].is_nan() {
+            nan += 1;
+        } else {
+            digest.push(row[0]);
+            stats.push(row[0]);
+        }
+    }
+
+    let fill = match missing {
+        Missing::ImputeMean if stats.n > 0 => Some(stats.mean),
+        Missing::ImputeMedian if stats.n > 0 => Some(digest.quantile(0.5)),
+        _ => None,
+    };
+
+    if let Some(fill) = fill {
+        for _ in 0..nan {
+            digest.push(fill);
         }
     }
 
-    data
+    Ok((digest, rows.missing_counts().clone()))
+}
+
+/// Matrix holds the parsed feature columns, the optional label column, and the names of
+/// the feature columns. Without a header the names are the original column indices.
+#[derive(Default, Debug, Clone)]
+pub struct Matrix {
+    pub header: Vec<String>,
+    pub xdata: Vec<Vec<f64>>,
+    pub ydata: Vec<f32>,
+    pub missing: MissingCounts,
+    /// the label encoding when some labels are not numbers, ydata then holds label indices
+    pub labels: Option<Labels>,
+    /// the number of rows of each group in order, with a group column
+    pub groups: Vec<u32>,
 }
 
 /// to_matrix parses a input and builds a Matrix. If 'ycol' is a valid column
 /// index, that column will be held out and used as the labels for the Matrix.
-pub fn to_matrix(raw_inputs: &str, ycol: usize, with_header: bool) -> (Vec<Vec<f64>>, Vec<f32>) {
-    let mut xdata = Vec::new();
-    let mut ydata = Vec::new();
-
-    for (index, line) in raw_inputs.split('\n').enumerate() {
-        if index == 0 && with_header {
-            continue;
-        }
+/// If columns is given, only the selected columns are used as features.
+///
+/// A row with a missing label is skipped unless the policy is to error. Missing
+/// features follow the policy, except skip-cell skips the whole row as every row of a
+/// matrix must be complete. When any label is not a number, all labels are encoded with
+/// Labels.
+pub fn to_matrix<R: BufRead>(
+    input: R,
+    ycol: usize,
+    with_header: bool,
+    columns: Option<&Columns>,
+    delimiter: Delimiter,
+    missing: Missing,
+) -> Result<Matrix, SeriesError> {
+    to_grouped_matrix(input, ycol, None, with_header, columns, delimiter, missing)
+}
 
-        if line == "\n" || line.is_empty() {
-            continue;
+/// to_grouped_matrix is to_matrix with an optional group column, e.g. the query id of a
+/// ranking, which is held out of the features like the labels. The rows of a group must be
+/// contiguous, and the size of each group is kept in Matrix::groups.
+pub fn to_grouped_matrix<R: BufRead>(
+    input: R,
+    ycol: usize,
+    group: Option<usize>,
+    with_header: bool,
+    columns: Option<&Columns>,
+    delimiter: Delimiter,
+    missing: Missing,
+) -> Result<Matrix, SeriesError> {
+    let mut matrix = Matrix::default();
+    let mut records = Records::new(input, delimiter);
+    let mut selected: Option<Vec<usize>> = None;
+
+    let held_out = |i: usize| i == ycol || Some(i) == group;
+    let mut last_group: Option<String> = None;
+    let mut seen_groups = HashSet::new();
+
+    let row_policy = match missing {
+        Missing::SkipCell => Missing::SkipRow,
+        m => m,
+    };
+
+    let names: Option<Vec<String>> = if with_header {
+        records
+            .next()
+            .transpose()?
+            .map(|names| names.iter().map(|n| n.trim().to_string()).collect())
+    } else {
+        None
+    };
+
+    while let Some(cols) = records.next() {
+        let cols = cols?;
+        let line = records.line;
+
+        // the selection is resolved against the first row, rows without a selection may
+        // still vary in length
+        if selected.is_none() {
+            let indices = select(columns, names.as_deref(), cols.len())?;
+
+            matrix.header = indices
+                .iter()
+                .filter(|i| !held_out(**i))
+                .map(|i| match &names {
+                    Some(n) if *i < n.len() => n[*i].to_string(),
+                    _ => i.to_string(),
+                })
+                .collect();
+
+            selected = Some(indices);
         }
 
-        let split = line.split(',');
+        // a label that is not a number is kept as a string, and encoded below
+        let label = if ycol < cols.len() {
+            match parse_value(&cols[ycol], line, missing) {
+                Ok(y) if y.is_nan() => {
+                    matrix.missing.values += 1;
+                    matrix.missing.rows += 1;
+                    continue;
+                }
+                Ok(y) => Some(Ok(y as f32)),
+                Err(SeriesError::Parse { .. }) => Some(Err(cols[ycol].as_str())),
+                Err(e) => return Err(e),
+            }
+        } else {
+            None
+        };
 
         let mut row = vec![];
 
-        for (index, val) in split.enumerate() {
-            let temp: Result<f64, _> = val.trim().parse();
-            match temp {
-                Ok(f) => {
-                    if index == ycol {
-                        ydata.push(f as f32)
-                    } else {
-                        row.push(f)
-                    }
+        if columns.is_some() {
+            for i in selected.as_ref().unwrap() {
+                if held_out(*i) {
+                    continue;
                 }
-                Err(_) => {
-                    eprintln!("error converting to float: {} at line {}", line, index);
-                    std::process::exit(1);
+
+                match cols.get(*i) {
+                    Some(cell) => row.push(parse_value(cell, line, missing)?),
+                    None => return Err(SeriesError::MissingColumn { column: *i, line }),
                 }
             }
+        } else {
+            for (i, cell) in cols.iter().enumerate() {
+                if !held_out(i) {
+                    row.push(parse_value(cell, line, missing)?);
+                }
+            }
+        }
+
+        if !matrix.missing.apply(&mut row, row_policy) {
+            continue;
+        }
+
+        if let Some(g) = group {
+            let id = match cols.get(g) {
+                Some(id) => id.trim(),
+                None => return Err(SeriesError::MissingColumn { column: g, line }),
+            };
+
+            if last_group.as_deref() == Some(id) {
+                *matrix.groups.last_mut().unwrap() += 1;
+            } else if !seen_groups.insert(id.to_string()) {
+                return Err(SeriesError::Group {
+                    group: id.to_string(),
+                    line,
+                });
+            } else {
+                matrix.groups.push(1);
+                last_group = Some(id.to_string());
+            }
         }
 
-        xdata.push(row);
+        // once a label is a string every label is encoded, in the order first seen until
+        // they are sorted at the end
+        let y = match (label, &mut matrix.labels) {
+            (None, _) => None,
+            (Some(Ok(y)), None) => Some(y),
+            (Some(Ok(y)), Some(labels)) => Some(labels.push(&y.to_string()) as f32),
+            (Some(Err(name)), labels) => {
+                let labels = labels.get_or_insert_with(|| Labels::encode(&mut matrix.ydata));
+                Some(labels.push(name) as f32)
+            }
+        };
+
+        if let Some(y) = y {
+            matrix.ydata.push(y);
+        }
+
+        matrix.xdata.push(row);
     }
 
-    (xdata, ydata)
+    if let Some(labels) = matrix.labels.as_mut() {
+        let order = labels.sort();
+        for y in matrix.ydata.iter_mut() {
+            *y = order[*y as usize] as f32;
+        }
+    }
+
+    impute(&mut matrix.xdata, missing);
+
+    Ok(matrix)
 }
 
 #[cfg(test)]
@@ -441,6 +1254,60 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_confusion_matrix_stats() {
+        // 0 is predicted 9 times and right 8, 1 is predicted 3 times and found in 2 of 3
+        let tuples = vec![
+            (1.0, 1.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 0.0),
+        ];
+        let matrix = confusion_matrix(&tuples, None).unwrap();
+        assert_eq!(matrix, vec![vec![2, 1], vec![1, 8]]);
+
+        let stats = confusion_matrix_stats(&matrix);
+        let one = &stats[1];
+
+        assert_eq!((one.label, one.support), (1, 3));
+        assert_eq!(
+            (one.tpr, one.fpr, one.precision),
+            (2.0 / 3.0, 1.0 / 9.0, 2.0 / 3.0)
+        );
+        assert_eq!((one.tnr, one.npv), (8.0 / 9.0, 8.0 / 9.0));
+        assert!((one.mcc - 5.0 / 9.0).abs() < 1e-6);
+        assert!((stats[0].balanced_accuracy - one.balanced_accuracy).abs() < 1e-6);
+
+        let summary = confusion_matrix_summary(&matrix);
+        assert_eq!(summary.accuracy, 10.0 / 12.0);
+        assert!((summary.mcc - one.mcc).abs() < 1e-6);
+        assert!((summary.kappa - one.kappa).abs() < 1e-6);
+        assert!((summary.kappa - 5.0 / 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_threshold_table_stats() {
+        let stats = threshold_table_stats(&[(0.9, 1.0), (0.4, 1.0), (0.6, 0.0), (0.1, 0.0)]);
+        let thresholds: Vec<String> = stats.iter().map(|row| row[0].to_string()).collect();
+        assert_eq!(thresholds.len(), 19);
+        assert_eq!(thresholds[0], "0.05");
+        assert_eq!(thresholds[6], "0.35");
+        assert_eq!(thresholds[8], "0.45");
+        assert_eq!(thresholds[18], "0.95");
+        assert!(thresholds.iter().all(|t| t.len() <= 4), "{:?}", thresholds);
+
+        // at 0.5, [t, precision, tpr, f1, fpr]
+        assert_eq!(stats[9], vec![0.5, 0.5, 0.5, 0.5, 0.5]);
+    }
+
     #[test]
     fn test_corrm() {
         let input = vec![
@@ -451,11 +1318,250 @@ mod tests {
             vec![39.0, 33.0, 12.0],
         ];
 
-        let m = correlation_matrix(&input);
+        let m = correlation_matrix(&input).unwrap();
 
         assert_eq!(m[0][0], 1.0);
         assert_eq!(m[1][0], 0.5184570956392384);
         assert_eq!(m[2][0], -0.7018864176470834);
         assert_eq!(m[2][1], -0.860940956122431);
     }
+
+    #[test]
+    fn test_columns() {
+        let header: Vec<String> = vec!["a", "b", "c", "d"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let c: Columns = "0,2".parse().unwrap();
+        assert_eq!(c.resolve(None, 4), Ok(vec![0, 2]));
+
+        let c: Columns = "1-3".parse().unwrap();
+        assert_eq!(c.resolve(None, 4), Ok(vec![1, 2, 3]));
+
+        let c: Columns = "2-".parse().unwrap();
+        assert_eq!(c.resolve(None, 4), Ok(vec![2, 3]));
+
+        let c: Columns = "d,a".parse().unwrap();
+        assert_eq!(c.resolve(Some(&header), 4), Ok(vec![3, 0]));
+        assert!(c.resolve(None, 4).is_err());
+
+        let c: Columns = "4".parse().unwrap();
+        assert!(c.resolve(None, 4).is_err());
+
+        assert!("3-1".parse::<Columns>().is_err());
+    }
+
+    #[test]
+    fn test_to_matrix_columns() {
+        let input = "a,b,c,y\n1,2,3,0\n4,5,6,1\n";
+        let columns: Columns = "c,a".parse().unwrap();
+
+        let m = to_matrix(
+            input.as_bytes(),
+            3,
+            true,
+            Some(&columns),
+            Delimiter::default(),
+            Missing::default(),
+        )
+        .unwrap();
+
+        assert_eq!(m.header, vec!["c", "a"]);
+        assert_eq!(m.xdata, vec![vec![3.0, 1.0], vec![6.0, 4.0]]);
+        assert_eq!(m.ydata, vec![0.0, 1.0]);
+
+        let input = "1,,0\n2,3,NA\n4,5,1\n";
+        let m = to_matrix(
+            input.as_bytes(),
+            2,
+            false,
+            None,
+            Delimiter::default(),
+            Missing::Nan,
+        )
+        .unwrap();
+
+        assert_eq!(m.xdata.len(), 2);
+        assert!(m.xdata[0][1].is_nan());
+        assert_eq!(m.ydata, vec![0.0, 1.0]);
+        assert_eq!((m.missing.values, m.missing.rows), (2, 1));
+
+        let e = to_vector(
+            "1\nx\n".as_bytes(),
+            false,
+            None,
+            Delimiter::default(),
+            Missing::Error,
+        );
+        assert!(matches!(e, Err(SeriesError::Parse { line: 2, .. })));
+
+        let e = to_vector(
+            "1\n\"2\n".as_bytes(),
+            false,
+            None,
+            Delimiter::default(),
+            Missing::Error,
+        );
+        assert!(matches!(e, Err(SeriesError::UnterminatedQuote { line: 2 })));
+    }
+
+    #[test]
+    fn test_to_matrix_group() {
+        let input = "q1,1,2,0\nq1,3,4,1\nq2,5,6,2\nq3,7,8,0\nq3,,9,1\n";
+        let m = to_grouped_matrix(
+            input.as_bytes(),
+            3,
+            Some(0),
+            false,
+            None,
+            Delimiter::default(),
+            Missing::SkipRow,
+        )
+        .unwrap();
+
+        assert_eq!(
+            m.xdata,
+            vec![
+                vec![1.0, 2.0],
+                vec![3.0, 4.0],
+                vec![5.0, 6.0],
+                vec![7.0, 8.0]
+            ]
+        );
+        assert_eq!(m.groups, vec![2, 1, 1]);
+
+        let input = "q1,1,0\nq2,2,1\nq1,3,0\n";
+        let e = to_grouped_matrix(
+            input.as_bytes(),
+            2,
+            Some(0),
+            false,
+            None,
+            Delimiter::default(),
+            Missing::default(),
+        );
+        assert!(matches!(e, Err(SeriesError::Group { line: 3, .. })));
+    }
+
+    #[test]
+    fn test_labels() {
+        let input = "1.5,b\n2,a\n3,\n4,b\n";
+        let m = to_matrix(
+            input.as_bytes(),
+            1,
+            false,
+            None,
+            Delimiter::default(),
+            Missing::Nan,
+        )
+        .unwrap();
+
+        assert_eq!(m.ydata, vec![1.0, 0.0, 1.0]);
+        assert_eq!(m.labels.unwrap().names(), ["a", "b"]);
+
+        // sparse classes are not padded with empty rows, a probability is of the second
+        let mut counts = ConfusionCounts::new(None);
+        counts.push(7.0, 7.0).unwrap();
+        counts.push(0.9, 7.0).unwrap();
+        counts.push_labels("7", "1").unwrap();
+
+        assert_eq!(counts.labels().names(), ["1", "7"]);
+        assert_eq!(counts.matrix(), vec![vec![2, 1], vec![0, 0]]);
+    }
+
+    #[test]
+    fn test_confusion_counts_probability() {
+        // a probability of string classes is of the second class in sorted order, yes
+        let mut counts = ConfusionCounts::new(None);
+        let mut indices = vec![];
+        for (p, a) in [("0.9", "yes"), ("0.8", "yes"), ("0.2", "no"), ("0.1", "no")] {
+            indices.push(counts.push_labels(p, a).unwrap());
+        }
+
+        assert_eq!(counts.labels().names(), ["no", "yes"]);
+        assert_eq!(counts.matrix(), vec![vec![2, 0], vec![0, 2]]);
+
+        let order = counts.order();
+        let sorted: Vec<_> = indices
+            .iter()
+            .map(|(p, a)| (order[*p], order[*a]))
+            .collect();
+        assert_eq!(sorted, vec![(1, 1), (1, 1), (0, 0), (0, 0)]);
+
+        // with a threshold, and labels predicted as well
+        let mut counts = ConfusionCounts::new(Some(0.85));
+        counts.push_labels("0.8", "b").unwrap();
+        counts.push_labels("a", "a").unwrap();
+        assert_eq!(counts.labels().names(), ["a", "b"]);
+        assert_eq!(counts.matrix(), vec![vec![0, 0], vec![1, 1]]);
+
+        // 0 and 1 classes are kept even when only one is seen
+        let mut counts = ConfusionCounts::new(None);
+        counts.push(0.7, 1.0).unwrap();
+        assert_eq!(counts.labels().names(), ["0", "1"]);
+        assert_eq!(counts.matrix(), vec![vec![1, 0], vec![0, 0]]);
+
+        let mut counts = ConfusionCounts::new(None);
+        counts.push_labels("0.7", "a").unwrap();
+        counts.push_labels("0.7", "b").unwrap();
+        assert!(counts.push_labels("0.7", "c").is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let input = "a,b\n1,4\n2,\n3,6\n4,8\n";
+        let m = to_matrix(
+            input.as_bytes(),
+            1000000,
+            true,
+            None,
+            Delimiter::default(),
+            Missing::Nan,
+        )
+        .unwrap();
+
+        let d = describe(m.clone(), Missing::SkipCell, &[0.25, 0.5]).unwrap();
+
+        assert_eq!(d.len(), 2);
+        assert_eq!((d[0].n, d[0].missing), (4, 0));
+        assert_eq!(d[0].percentiles, vec![(0.25, 1.75), (0.5, 2.5)]);
+        assert_eq!((d[1].n, d[1].missing), (3, 1));
+        assert_eq!(d[1].mean, 6.0);
+        assert_eq!(d[1].skewness, 0.0);
+
+        let d = describe(m.clone(), Missing::SkipRow, &[]).unwrap();
+        assert_eq!((d[0].n, d[0].missing), (3, 0));
+        assert_eq!(d[0].max, 4.0);
+
+        let mut rows = Rows::new(input.as_bytes(), Delimiter::default(), true, None)
+            .unwrap()
+            .missing(Missing::Nan);
+        let mut approx = Describe::new(Missing::ImputeMean, 0, 100.0);
+        for row in &mut rows {
+            approx.push(row.unwrap()).unwrap();
+        }
+        assert!(approx.estimated());
+
+        let a = approx.summaries(&[0.5]).unwrap();
+        let d = describe(m, Missing::ImputeMean, &[0.5]).unwrap();
+
+        for (a, d) in a.iter().zip(&d) {
+            assert_eq!((a.n, a.missing, a.median), (d.n, d.missing, d.median));
+            assert!((a.var - d.var).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_histogram() {
+        let input = vec![1.0, 2.0, 2.5, 3.0, 4.0, 5.0];
+
+        let h = histogram(&input, 4);
+
+        assert_eq!(h.len(), 4);
+        assert_eq!(h[0], (1.0, 2.0, 1));
+        assert_eq!(h[1], (2.0, 3.0, 2));
+        assert_eq!(h[2], (3.0, 4.0, 1));
+        assert_eq!(h[3], (4.0, 5.0, 2));
+    }
 }
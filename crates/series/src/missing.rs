@@ -0,0 +1,222 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// cells holding one of these, or nothing at all, are treated as missing values
+const MISSING: [&str; 8] = ["", "NA", "N/A", "NaN", "nan", "null", "NULL", "None"];
+
+pub fn is_missing(cell: &str) -> bool {
+    MISSING.contains(&cell.trim())
+}
+
+/// Missing is the policy applied to missing values in the input. ImputeMean and
+/// ImputeMedian need the statistics of the whole column, so they are applied once the
+/// input has been read, see impute. Nan keeps missing values as NaN, e.g. for XGBoost
+/// which handles missing values natively.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Missing {
+    #[default]
+    Error,
+    SkipRow,
+    SkipCell,
+    ImputeMean,
+    ImputeMedian,
+    ImputeConst(f64),
+    Nan,
+}
+
+impl FromStr for Missing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Missing::Error),
+            "skip-row" => Ok(Missing::SkipRow),
+            "skip-cell" => Ok(Missing::SkipCell),
+            "impute-mean" => Ok(Missing::ImputeMean),
+            "impute-median" => Ok(Missing::ImputeMedian),
+            "impute-const" => Ok(Missing::ImputeConst(0.0)),
+            "nan" => Ok(Missing::Nan),
+            _ => match s.strip_prefix("impute-const=") {
+                Some(c) => match c.trim().parse() {
+                    Ok(c) => Ok(Missing::ImputeConst(c)),
+                    Err(_) => Err(format!("invalid constant for impute-const: {}", c)),
+                },
+                None => Err(format!(
+                    "invalid missing value policy {}, expected error, skip-row, skip-cell, \
+                     impute-mean, impute-median, impute-const[=value] or nan",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Missing::Error => write!(f, "error"),
+            Missing::SkipRow => write!(f, "skip-row"),
+            Missing::SkipCell => write!(f, "skip-cell"),
+            Missing::ImputeMean => write!(f, "impute-mean"),
+            Missing::ImputeMedian => write!(f, "impute-median"),
+            Missing::ImputeConst(c) => write!(f, "impute-const={}", c),
+            Missing::Nan => write!(f, "nan"),
+        }
+    }
+}
+
+/// MissingCounts tracks how many values were missing and how many rows were skipped
+#[derive(Default, Debug, Clone)]
+pub struct MissingCounts {
+    pub values: usize,
+    pub rows: usize,
+}
+
+impl MissingCounts {
+    /// apply applies the row level part of the policy to a parsed row, where missing values
+    /// are NaN. false is returned if the row should be skipped.
+    pub fn apply(&mut self, row: &mut [f64], missing: Missing) -> bool {
+        let n = row.iter().filter(|x| x.is_nan()).count();
+        if n == 0 {
+            return true;
+        }
+
+        self.values += n;
+
+        match missing {
+            Missing::SkipRow => {
+                self.rows += 1;
+                false
+            }
+
+            Missing::ImputeConst(c) => {
+                for x in row.iter_mut().filter(|x| x.is_nan()) {
+                    *x = c;
+                }
+                true
+            }
+
+            _ => true,
+        }
+    }
+
+    /// warning describes the affected values, None if there were no missing values
+    pub fn warning(&self, missing: Missing) -> Option<String> {
+        if self.values == 0 {
+            return None;
+        }
+
+        if self.rows > 0 {
+            Some(format!(
+                "{} missing values ({}), {} rows skipped",
+                self.values, missing, self.rows
+            ))
+        } else {
+            Some(format!("{} missing values ({})", self.values, missing))
+        }
+    }
+}
+
+/// fill_value is the mean or median of the values that are present
+fn fill_value(mut values: Vec<f64>, missing: Missing) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    if missing == Missing::ImputeMean {
+        return Some(values.iter().sum::<f64>() / values.len() as f64);
+    }
+
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mid = values.len() / 2;
+
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// impute_vector replaces the missing values of a single vector with its mean or median.
+/// Any other policy leaves the data as it is.
+pub fn impute_vector(data: &mut [f64], missing: Missing) {
+    if missing != Missing::ImputeMean && missing != Missing::ImputeMedian {
+        return;
+    }
+
+    let values = data.iter().filter(|x| !x.is_nan()).copied().collect();
+
+    if let Some(fill) = fill_value(values, missing) {
+        for x in data.iter_mut().filter(|x| x.is_nan()) {
+            *x = fill;
+        }
+    }
+}
+
+/// impute replaces the missing values of each column with the mean or median of that
+/// column. Any other policy leaves the data as it is.
+pub fn impute(data: &mut [Vec<f64>], missing: Missing) {
+    if missing != Missing::ImputeMean && missing != Missing::ImputeMedian {
+        return;
+    }
+
+    let cols = data.iter().map(|row| row.len()).max().unwrap_or(0);
+
+    for col in 0..cols {
+        let values = data
+            .iter()
+            .filter_map(|row| row.get(col))
+            .filter(|x| !x.is_nan())
+            .copied()
+            .collect();
+
+        let fill = match fill_value(values, missing) {
+            Some(fill) => fill,
+            None => continue,
+        };
+
+        for row in data.iter_mut() {
+            if let Some(x) = row.get_mut(col) {
+                if x.is_nan() {
+                    *x = fill;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_impute() {
+        let nan = f64::NAN;
+        let mut data = vec![
+            vec![1.0, nan],
+            vec![nan, 2.0],
+            vec![3.0, 4.0],
+            vec![8.0, 9.0],
+        ];
+
+        let mut mean = data.clone();
+        impute(&mut mean, Missing::ImputeMean);
+        assert_eq!(mean[1][0], 4.0);
+        assert_eq!(mean[0][1], 5.0);
+
+        impute(&mut data, Missing::ImputeMedian);
+        assert_eq!(data[1][0], 3.0);
+        assert_eq!(data[0][1], 4.0);
+
+        let mut counts = MissingCounts::default();
+        let mut row = vec![nan, 1.0, nan];
+        assert!(counts.apply(&mut row, Missing::ImputeConst(-1.0)));
+        assert_eq!(row, vec![-1.0, 1.0, -1.0]);
+        assert!(!counts.apply(&mut [nan], Missing::SkipRow));
+        assert_eq!((counts.values, counts.rows), (3, 1));
+
+        assert_eq!("impute-const=2.5".parse(), Ok(Missing::ImputeConst(2.5)));
+        assert!("skip".parse::<Missing>().is_err());
+    }
+}
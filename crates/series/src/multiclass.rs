@@ -0,0 +1,197 @@
+use crate::roc;
+
+/// ClassReport holds the one-vs-rest stats of a single class. Precision, recall and F1 are 0
+/// when undefined, as in scikit-learn, and the AUC is NaN when the class is absent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassReport {
+    pub label: usize,
+    pub support: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub auc: f64,
+}
+
+/// Average is the precision, recall and F1 averaged over the classes. Macro weighs each class
+/// the same, weighted by its support, and micro sums the counts of every class first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Average {
+    pub name: &'static str,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+/// Multiclass holds the evaluation of softprob output, a probability for each class
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multiclass {
+    pub n: usize,
+    /// counts[predicted][actual], the predicted class is the most probable one
+    pub counts: Vec<Vec<u32>>,
+    /// (k, accuracy) where a row is right when its class is among the k most probable
+    pub top_k: Vec<(usize, f64)>,
+    /// the mean negative log probability of the actual class, clipped to [1e-15, 1 - 1e-15]
+    pub log_loss: f64,
+    pub classes: Vec<ClassReport>,
+    pub averages: Vec<Average>,
+}
+
+impl Multiclass {
+    /// matrix returns the confusion matrix in descending class order, as ConfusionCounts does
+    pub fn matrix(&self) -> Vec<Vec<u32>> {
+        let mut matrix = self.counts.clone();
+
+        for row in matrix.iter_mut() {
+            row.reverse();
+        }
+        matrix.reverse();
+
+        matrix
+    }
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
+
+fn f1(precision: f64, recall: f64) -> f64 {
+    if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    }
+}
+
+/// multiclass evaluates rows of class probabilities, each with the actual class. Every row
+/// must hold the same number of probabilities and the classes must be smaller than it. The
+/// top-k accuracy is computed for k from 1 to top_k.
+pub fn multiclass(rows: &[(Vec<f64>, usize)], top_k: usize) -> Multiclass {
+    let size = rows.first().map(|(p, _)| p.len()).unwrap_or(0);
+    let eps = 1e-15;
+
+    let mut counts = vec![vec![0; size]; size];
+    let mut hits = vec![0; top_k];
+    let mut log_loss = 0.0;
+
+    for (probabilities, actual) in rows {
+        // classes from the most to the least probable, ties in class order
+        let mut order: Vec<usize> = (0..size).collect();
+        order.sort_by(|a, b| probabilities[*b].partial_cmp(&probabilities[*a]).unwrap());
+
+        counts[order[0]][*actual] += 1;
+
+        if let Some(rank) = order.iter().position(|c| c == actual) {
+            for hit in hits.iter_mut().skip(rank) {
+                *hit += 1;
+            }
+        }
+
+        log_loss -= probabilities[*actual].clamp(eps, 1.0 - eps).ln();
+    }
+
+    let n = rows.len();
+    let mut classes = vec![];
+    let (mut tp, mut predicted) = (0, 0);
+
+    for label in 0..size {
+        let correct = counts[label][label] as usize;
+        let support: usize = counts.iter().map(|row| row[label] as usize).sum();
+        let positives: usize = counts[label].iter().map(|c| *c as usize).sum();
+
+        let scores: Vec<(f64, bool)> = rows.iter().map(|(p, a)| (p[label], *a == label)).collect();
+
+        let precision = ratio(correct, positives);
+        let recall = ratio(correct, support);
+
+        classes.push(ClassReport {
+            label,
+            support,
+            precision,
+            recall,
+            f1: f1(precision, recall),
+            auc: roc(&scores).auc,
+        });
+
+        tp += correct;
+        predicted += positives;
+    }
+
+    let mean =
+        |f: &dyn Fn(&ClassReport) -> f64| classes.iter().map(f).sum::<f64>() / classes.len() as f64;
+    let weighted = |f: &dyn Fn(&ClassReport) -> f64| {
+        classes.iter().map(|c| f(c) * c.support as f64).sum::<f64>() / n as f64
+    };
+
+    let micro = ratio(tp, predicted);
+
+    let averages = vec![
+        Average {
+            name: "macro",
+            precision: mean(&|c| c.precision),
+            recall: mean(&|c| c.recall),
+            f1: mean(&|c| c.f1),
+        },
+        Average {
+            name: "micro",
+            precision: micro,
+            recall: micro,
+            f1: micro,
+        },
+        Average {
+            name: "weighted",
+            precision: weighted(&|c| c.precision),
+            recall: weighted(&|c| c.recall),
+            f1: weighted(&|c| c.f1),
+        },
+    ];
+
+    Multiclass {
+        n,
+        counts,
+        top_k: hits
+            .into_iter()
+            .enumerate()
+            .map(|(k, hit)| (k + 1, ratio(hit, n)))
+            .collect(),
+        log_loss: log_loss / n as f64,
+        classes,
+        averages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_multiclass() {
+        let rows = vec![
+            (vec![0.7, 0.2, 0.1], 0),
+            (vec![0.2, 0.5, 0.3], 2),
+            (vec![0.1, 0.8, 0.1], 1),
+            (vec![0.3, 0.3, 0.4], 2),
+        ];
+
+        let m = multiclass(&rows, 2);
+
+        assert_eq!(m.counts, vec![vec![1, 0, 0], vec![0, 1, 1], vec![0, 0, 1]]);
+        assert_eq!(m.top_k, vec![(1, 0.75), (2, 1.0)]);
+
+        let expected = -(0.7f64.ln() + 0.3f64.ln() + 0.8f64.ln() + 0.4f64.ln()) / 4.0;
+        assert!((m.log_loss - expected).abs() < 1e-12);
+
+        // class 1 is predicted twice and right once, class 2 is found once in two
+        assert_eq!((m.classes[1].precision, m.classes[1].recall), (0.5, 1.0));
+        assert_eq!((m.classes[2].precision, m.classes[2].recall), (1.0, 0.5));
+        assert_eq!(m.classes[0].auc, 1.0);
+
+        assert_eq!(m.averages[1].f1, 0.75);
+        assert!((m.averages[0].recall - 2.5 / 3.0).abs() < 1e-12);
+        assert!((m.averages[2].recall - 0.75).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::SeriesError;
+
+/// OnlineStats computes the count, min, max, mean, variance, skewness and kurtosis of a
+/// stream of values in a single pass using Welford's algorithm, extended to the third and
+/// fourth central moments, so memory use does not grow with the input.
+#[derive(Default, Debug, Clone)]
+pub struct OnlineStats {
+    pub n: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl OnlineStats {
+    pub fn push(&mut self, x: f64) {
+        if self.n == 0 {
+            self.min = x;
+            self.max = x;
+        } else {
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+        }
+
+        let n1 = self.n as f64;
+        self.n += 1;
+        let n = self.n as f64;
+
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term = delta * delta_n * n1;
+
+        // the higher moments are updated first as they use the previous m2 and m3
+        self.m4 += term * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2
+            - 4.0 * delta_n * self.m3;
+        self.m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term;
+        self.mean += delta_n;
+    }
+
+    /// population variance
+    pub fn var(&self) -> f64 {
+        if self.n == 0 {
+            return 0.0;
+        }
+
+        self.m2 / self.n as f64
+    }
+
+    pub fn stdev(&self) -> f64 {
+        self.var().sqrt()
+    }
+
+    /// population skewness, 0 for a constant input
+    pub fn skewness(&self) -> f64 {
+        if self.m2 == 0.0 {
+            return 0.0;
+        }
+
+        (self.n as f64).sqrt() * self.m3 / self.m2.powf(1.5)
+    }
+
+    /// population excess kurtosis, 0 for a normal distribution and for a constant input
+    pub fn kurtosis(&self) -> f64 {
+        if self.m2 == 0.0 {
+            return 0.0;
+        }
+
+        self.n as f64 * self.m4 / (self.m2 * self.m2) - 3.0
+    }
+}
+
+/// OnlineCorrelation accumulates the means and co-moments of every pair of columns, one row
+/// at a time, from which the Pearson correlation matrix is computed.
+#[derive(Debug, Clone)]
+pub struct OnlineCorrelation {
+    n: f64,
+    mean: Vec<f64>,
+    comoment: Vec<Vec<f64>>,
+}
+
+impl OnlineCorrelation {
+    pub fn new(cols: usize) -> Self {
+        OnlineCorrelation {
+            n: 0.0,
+            mean: vec![0.0; cols],
+            comoment: vec![vec![0.0; cols]; cols],
+        }
+    }
+
+    pub fn push(&mut self, row: &[f64]) -> Result<(), SeriesError> {
+        let cols = self.mean.len();
+        if row.len() != cols {
+            return Err(SeriesError::RowLength {
+                expected: cols,
+                found: row.len(),
+            });
+        }
+
+        self.n += 1.0;
+
+        // deviation from the previous mean, then update the means
+        let delta: Vec<f64> = row.iter().zip(&self.mean).map(|(x, m)| x - m).collect();
+        for (m, d) in self.mean.iter_mut().zip(&delta) {
+            *m += d / self.n;
+        }
+
+        for (i, d) in delta.iter().enumerate() {
+            for (j, x) in row.iter().enumerate().skip(i) {
+                self.comoment[i][j] += d * (x - self.mean[j]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// matrix returns the lower triangle of the correlation matrix, the upper triangle is 0
+    pub fn matrix(&self) -> Vec<Vec<f64>> {
+        let cols = self.mean.len();
+        let mut out = vec![vec![0.0; cols]; cols];
+
+        for (j, row) in out.iter_mut().enumerate() {
+            for (i, r) in row.iter_mut().enumerate().take(j + 1) {
+                *r = self.comoment[i][j] / (self.comoment[i][i] * self.comoment[j][j]).sqrt();
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_online() {
+        let input = vec![
+            vec![45.0, 38.0, 10.0],
+            vec![37.0, 31.0, 15.0],
+            vec![42.0, 26.0, 17.0],
+            vec![35.0, 28.0, 21.0],
+            vec![39.0, 33.0, 12.0],
+        ];
+
+        let mut stats = OnlineStats::default();
+        let mut cor = OnlineCorrelation::new(3);
+
+        for row in &input {
+            stats.push(row[0]);
+            cor.push(row).unwrap();
+        }
+
+        assert_eq!(stats.n, 5);
+        assert_eq!(stats.min, 35.0);
+        assert_eq!(stats.max, 45.0);
+        assert!((stats.mean - 39.6).abs() < 1e-12);
+        assert!((stats.var() - 12.64).abs() < 1e-12);
+        assert!((stats.skewness() - 0.24994).abs() < 1e-5);
+        assert!((stats.kurtosis() + 1.27620).abs() < 1e-5);
+
+        let expected = crate::correlation_matrix(&input).unwrap();
+        for (row, expected) in cor.matrix().iter().zip(&expected) {
+            for (r, e) in row.iter().zip(expected) {
+                assert!((r - e).abs() < 1e-12);
+            }
+        }
+    }
+}
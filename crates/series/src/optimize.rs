@@ -0,0 +1,263 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Roc;
+
+/// Cost is the cost of each cell of a binary confusion matrix, e.g. "fp=5,fn=1". Cells that
+/// aren't given cost 0, except fp and fn which cost 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cost {
+    pub tp: f64,
+    pub fp: f64,
+    pub fn_: f64,
+    pub tn: f64,
+}
+
+impl Default for Cost {
+    fn default() -> Self {
+        Cost {
+            tp: 0.0,
+            fp: 1.0,
+            fn_: 1.0,
+            tn: 0.0,
+        }
+    }
+}
+
+impl FromStr for Cost {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cost = Cost::default();
+
+        for item in s.split(',') {
+            let (cell, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid cost {}, expected e.g. fp=5,fn=1", item))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid cost {}", item))?;
+
+            match cell.trim() {
+                "tp" => cost.tp = value,
+                "fp" => cost.fp = value,
+                "fn" => cost.fn_ = value,
+                "tn" => cost.tn = value,
+                c => {
+                    return Err(format!(
+                        "invalid cost cell {}, expected tp, fp, fn or tn",
+                        c
+                    ))
+                }
+            }
+        }
+
+        Ok(cost)
+    }
+}
+
+/// Objective is what a threshold is chosen for. The targets pick the threshold with the best
+/// precision among those with at least the given recall, or the best recall among those with
+/// at least the given precision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    F1,
+    FBeta(f64),
+    Youden,
+    Mcc,
+    Cost(Cost),
+    PrecisionAtRecall(f64),
+    RecallAtPrecision(f64),
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => match value.trim().parse::<f64>() {
+                Ok(v) => (name.trim(), Some(v)),
+                Err(_) => return Err(format!("invalid value in {}", s)),
+            },
+            None => (s.trim(), None),
+        };
+
+        match (name, value) {
+            ("f1", None) => Ok(Objective::F1),
+            ("fbeta", Some(beta)) if beta > 0.0 => Ok(Objective::FBeta(beta)),
+            ("youden", None) => Ok(Objective::Youden),
+            ("mcc", None) => Ok(Objective::Mcc),
+            ("cost", None) => Ok(Objective::Cost(Cost::default())),
+            ("precision-at-recall", Some(r)) if (0.0..=1.0).contains(&r) => {
+                Ok(Objective::PrecisionAtRecall(r))
+            }
+            ("recall-at-precision", Some(p)) if (0.0..=1.0).contains(&p) => {
+                Ok(Objective::RecallAtPrecision(p))
+            }
+            _ => Err(format!(
+                "invalid objective {}, expected f1, fbeta=B, youden, mcc, cost, \
+                 precision-at-recall=R or recall-at-precision=P",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::F1 => write!(f, "f1"),
+            Objective::FBeta(beta) => write!(f, "fbeta={}", beta),
+            Objective::Youden => write!(f, "youden"),
+            Objective::Mcc => write!(f, "mcc"),
+            Objective::Cost(_) => write!(f, "cost"),
+            Objective::PrecisionAtRecall(r) => write!(f, "precision-at-recall={}", r),
+            Objective::RecallAtPrecision(p) => write!(f, "recall-at-precision={}", p),
+        }
+    }
+}
+
+/// Optimum is the threshold chosen for an objective, with the confusion matrix at it and the
+/// value of the objective, e.g. the F1 or the total cost
+#[derive(Debug, Clone, PartialEq)]
+pub struct Optimum {
+    pub threshold: f64,
+    pub tp: usize,
+    pub fp: usize,
+    pub fn_: usize,
+    pub tn: usize,
+    pub value: f64,
+}
+
+impl Optimum {
+    /// matrix returns the confusion matrix in the layout of ConfusionCounts, TP FP over FN TN
+    pub fn matrix(&self) -> Vec<Vec<u32>> {
+        vec![
+            vec![self.tp as u32, self.fp as u32],
+            vec![self.fn_ as u32, self.tn as u32],
+        ]
+    }
+}
+
+fn ratio(a: f64, b: f64) -> f64 {
+    if b == 0.0 {
+        0.0
+    } else {
+        a / b
+    }
+}
+
+/// optimize searches every threshold of a ROC curve, i.e. every distinct score, for the best
+/// value of the objective. Ties go to the higher threshold. None when no threshold meets a
+/// precision or recall target.
+pub fn optimize(roc: &Roc, objective: Objective) -> Option<Optimum> {
+    let mut best: Option<Optimum> = None;
+
+    for point in &roc.points {
+        let (tp, fp) = (point.tp as f64, point.fp as f64);
+        let fn_ = (roc.positives - point.tp) as f64;
+        let tn = (roc.negatives - point.fp) as f64;
+
+        let precision = ratio(tp, tp + fp);
+        let recall = ratio(tp, tp + fn_);
+
+        // the value of the objective, None when a target is not met
+        let value = match objective {
+            Objective::F1 => Some(ratio(2.0 * precision * recall, precision + recall)),
+            Objective::FBeta(beta) => {
+                let b2 = beta * beta;
+                Some(ratio(
+                    (1.0 + b2) * precision * recall,
+                    b2 * precision + recall,
+                ))
+            }
+            Objective::Youden => Some(recall - ratio(fp, fp + tn)),
+            Objective::Mcc => Some(ratio(
+                tp * tn - fp * fn_,
+                ((tp + fp) * (tp + fn_) * (tn + fp) * (tn + fn_)).sqrt(),
+            )),
+            Objective::Cost(c) => Some(c.tp * tp + c.fp * fp + c.fn_ * fn_ + c.tn * tn),
+            Objective::PrecisionAtRecall(r) => Some(precision).filter(|_| recall >= r),
+            Objective::RecallAtPrecision(p) => {
+                Some(recall).filter(|_| tp + fp > 0.0 && precision >= p)
+            }
+        };
+
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+
+        // a cost is minimized, everything else maximized
+        let better = match &best {
+            Some(b) => match objective {
+                Objective::Cost(_) => value < b.value,
+                _ => value > b.value,
+            },
+            None => true,
+        };
+
+        if better {
+            best = Some(Optimum {
+                threshold: point.threshold,
+                tp: point.tp,
+                fp: point.fp,
+                fn_: fn_ as usize,
+                tn: tn as usize,
+                value,
+            });
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::roc;
+
+    #[test]
+    fn test_optimize() {
+        let input = vec![
+            (0.9, true),
+            (0.8, true),
+            (0.7, false),
+            (0.6, true),
+            (0.4, false),
+            (0.3, true),
+            (0.2, false),
+            (0.1, false),
+        ];
+        let r = roc(&input);
+
+        // at 0.3 every positive and 2 false positives
+        let best = optimize(&r, Objective::F1).unwrap();
+        assert_eq!((best.threshold, best.tp, best.fp), (0.3, 4, 2));
+        assert!((best.value - 0.8).abs() < 1e-12);
+        assert_eq!(best.matrix(), vec![vec![4, 2], vec![0, 2]]);
+
+        // 0.8 and 0.3 tie on both, the higher threshold wins
+        assert_eq!(optimize(&r, Objective::Youden).unwrap().threshold, 0.8);
+        assert_eq!(optimize(&r, Objective::Mcc).unwrap().threshold, 0.8);
+
+        // false positives are expensive, so only the two safest are predicted positive
+        let cost: Cost = "fp=5".parse().unwrap();
+        let best = optimize(&r, Objective::Cost(cost)).unwrap();
+        assert_eq!((best.threshold, best.value), (0.8, 2.0));
+
+        let best = optimize(&r, Objective::PrecisionAtRecall(1.0)).unwrap();
+        assert_eq!((best.threshold, best.value), (0.3, 4.0 / 6.0));
+        assert_eq!(
+            optimize(&r, Objective::RecallAtPrecision(1.0))
+                .unwrap()
+                .value,
+            0.5
+        );
+
+        assert_eq!("fbeta=2".parse::<Objective>(), Ok(Objective::FBeta(2.0)));
+        assert!("fbeta".parse::<Objective>().is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+/// QuantileType is one of the nine sample quantile definitions of Hyndman and Fan (1996),
+/// numbered as in R's quantile(). Types 1 to 3 are discontinuous and always return a value
+/// of the input, types 4 to 9 interpolate between the two closest values. Type 7 is the
+/// default, as in R and numpy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantileType(u8);
+
+impl Default for QuantileType {
+    fn default() -> Self {
+        QuantileType(7)
+    }
+}
+
+impl FromStr for QuantileType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse() {
+            Ok(t) if (1..=9).contains(&t) => Ok(QuantileType(t)),
+            _ => Err(format!("invalid quantile type {}, expected 1 to 9", s)),
+        }
+    }
+}
+
+/// quantile returns the p quantile of a sorted, non empty input, p is clamped to [0, 1]
+pub fn quantile(sorted: &[f64], p: f64, typ: QuantileType) -> f64 {
+    let n = sorted.len() as f64;
+    let p = p.clamp(0.0, 1.0);
+
+    // the value at a 1 based position, positions outside the input are clamped to it
+    let x = |j: f64| sorted[(j.max(1.0).min(n) as usize) - 1];
+
+    // R's fuzz, so a position that should be a whole number is not lost to rounding
+    let fuzz = 4.0 * f64::EPSILON;
+
+    if typ.0 <= 3 {
+        let m = if typ.0 == 3 { -0.5 } else { 0.0 };
+        let np = n * p + m;
+        let j = (np + fuzz).floor();
+        let g = if (np - j).abs() < fuzz { 0.0 } else { np - j };
+
+        let gamma = match typ.0 {
+            1 => (g > 0.0) as u8 as f64,
+            2 if g > 0.0 => 1.0,
+            2 => 0.5,
+            _ if g == 0.0 && j % 2.0 == 0.0 => 0.0,
+            _ => 1.0,
+        };
+
+        return (1.0 - gamma) * x(j) + gamma * x(j + 1.0);
+    }
+
+    // the 1 based position of the quantile, (alpha, beta) as in Hyndman and Fan
+    let (alpha, beta) = match typ.0 {
+        4 => (0.0, 1.0),
+        5 => (0.5, 0.5),
+        6 => (0.0, 0.0),
+        7 => (1.0, 1.0),
+        8 => (1.0 / 3.0, 1.0 / 3.0),
+        _ => (3.0 / 8.0, 3.0 / 8.0),
+    };
+
+    let h = (n + 1.0 - alpha - beta) * p + alpha;
+    let h = h.max(1.0).min(n);
+    let j = (h + fuzz).floor();
+    let g = if (h - j).abs() < fuzz { 0.0 } else { h - j };
+
+    x(j) + g * (x(j + 1.0) - x(j))
+}
+
+/// quantiles sorts a copy of the input and returns the quantile of each probability, the
+/// input must be non empty
+pub fn quantiles(input: &[f64], probabilities: &[f64], typ: QuantileType) -> Vec<f64> {
+    let mut sorted = input.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    probabilities
+        .iter()
+        .map(|p| quantile(&sorted, *p, typ))
+        .collect()
+}
+
+/// k_quantiles returns the k - 1 probabilities splitting a distribution into k equal parts
+pub fn k_quantiles(k: usize) -> Vec<f64> {
+    (1..k).map(|i| i as f64 / k as f64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_quantile_types() {
+        let input = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+
+        // expected values follow R, quantile(x, c(0.25, 0.5), type = t)
+        let expected = [
+            (1, 1.0, 3.0),
+            (2, 1.5, 3.5),
+            (3, 1.0, 3.0),
+            (4, 1.0, 3.0),
+            (5, 1.5, 3.5),
+            (6, 1.25, 3.5),
+            (7, 1.75, 3.5),
+            (8, 1.416667, 3.5),
+            (9, 1.4375, 3.5),
+        ];
+
+        for (t, q25, q50) in expected {
+            let typ = QuantileType(t);
+            let q = quantiles(&input, &[0.0, 0.25, 0.5, 1.0], typ);
+
+            assert!((q[1] - q25).abs() < 1e-6, "type {} q25 {}", t, q[1]);
+            assert!((q[2] - q50).abs() < 1e-6, "type {} q50 {}", t, q[2]);
+            assert_eq!((q[0], q[3]), (1.0, 9.0));
+        }
+
+        assert_eq!(k_quantiles(4), vec![0.25, 0.5, 0.75]);
+        assert!("0".parse::<QuantileType>().is_err());
+    }
+}
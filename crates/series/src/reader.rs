@@ -0,0 +1,475 @@
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::{
+    impute, is_missing, parse_value, select, Columns, Missing, MissingCounts, SeriesError,
+};
+
+/// Delimiter controls how a line of input is split into fields. Csv follows RFC 4180, fields
+/// may be quoted to hold the delimiter, quotes or line breaks. Tsv splits on tabs with no
+/// quoting, and Whitespace splits on any run of whitespace.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Delimiter {
+    Csv(char),
+    Tsv,
+    Whitespace,
+}
+
+impl Default for Delimiter {
+    fn default() -> Self {
+        Delimiter::Csv(',')
+    }
+}
+
+impl FromStr for Delimiter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Delimiter::Csv(',')),
+            "tsv" | "tab" | "\t" | "\\t" => Ok(Delimiter::Tsv),
+            "ws" | "whitespace" => Ok(Delimiter::Whitespace),
+            _ => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some('"'), None) => {
+                        Err("the quote character can't be a delimiter".to_string())
+                    }
+                    (Some(c), None) => Ok(Delimiter::Csv(c)),
+                    _ => Err(format!(
+                        "invalid delimiter {}, expected a single character, csv, tsv or ws",
+                        s
+                    )),
+                }
+            }
+        }
+    }
+}
+
+/// split_csv splits a single RFC 4180 record. None is returned when a quoted field is still
+/// open at the end of the record, the record then continues on the next line.
+fn split_csv(record: &str, delimiter: char) -> Option<Vec<String>> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = record.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                // a doubled quote is an escaped quote
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' {
+            in_quotes = true;
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(c);
+        }
+    }
+
+    if in_quotes {
+        return None;
+    }
+
+    fields.push(field);
+    Some(fields)
+}
+
+/// Records iterates over the records of the input, splitting each into its fields. The
+/// input is read a line at a time, so memory use is bounded by the longest record. Blank
+/// lines are skipped, and both LF and CRLF line endings are accepted. Iteration stops after
+/// the first error.
+pub struct Records<R> {
+    input: R,
+    delimiter: Delimiter,
+    buf: String,
+    /// the line number of the last line read, starting at 1
+    pub line: usize,
+    failed: bool,
+}
+
+impl<R: BufRead> Records<R> {
+    pub fn new(input: R, delimiter: Delimiter) -> Self {
+        Records {
+            input,
+            delimiter,
+            buf: String::new(),
+            line: 0,
+            failed: false,
+        }
+    }
+
+    /// read_line reads the next line into buf without its line ending, false at end of input
+    fn read_line(&mut self) -> Result<bool, SeriesError> {
+        self.buf.clear();
+
+        match self.input.read_line(&mut self.buf) {
+            Ok(0) => Ok(false),
+            Ok(_) => {
+                self.line += 1;
+
+                if self.buf.ends_with('\n') {
+                    self.buf.pop();
+                    if self.buf.ends_with('\r') {
+                        self.buf.pop();
+                    }
+                }
+
+                Ok(true)
+            }
+            Err(e) => Err(SeriesError::Io {
+                line: self.line + 1,
+                source: e,
+            }),
+        }
+    }
+
+    /// next_record reads and splits the next non blank record, None at end of input
+    fn next_record(&mut self) -> Result<Option<Vec<String>>, SeriesError> {
+        loop {
+            if !self.read_line()? {
+                return Ok(None);
+            }
+
+            if !self.buf.trim().is_empty() {
+                break;
+            }
+        }
+
+        match self.delimiter {
+            Delimiter::Tsv => Ok(Some(self.buf.split('\t').map(String::from).collect())),
+
+            Delimiter::Whitespace => Ok(Some(
+                self.buf.split_whitespace().map(String::from).collect(),
+            )),
+
+            Delimiter::Csv(d) => {
+                let start = self.line;
+                let mut record = self.buf.to_string();
+
+                loop {
+                    if let Some(fields) = split_csv(&record, d) {
+                        return Ok(Some(fields));
+                    }
+
+                    if !self.read_line()? {
+                        return Err(SeriesError::UnterminatedQuote { line: start });
+                    }
+
+                    record.push('\n');
+                    record.push_str(&self.buf);
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<Vec<String>, SeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let record = self.next_record();
+        self.failed = record.is_err();
+        record.transpose()
+    }
+}
+
+/// Rows reads numeric rows from the input one at a time. The header, if any, is read when
+/// the reader is created and the column selection is resolved against the first row.
+/// Without a selection every column of each row is returned. Missing values are NaN
+/// unless the missing value policy skips the row or imputes a constant.
+pub struct Rows<R> {
+    records: Records<R>,
+    header: Option<Vec<String>>,
+    columns: Option<Columns>,
+    selected: Option<Vec<usize>>,
+    without: Option<usize>,
+    missing: Missing,
+    counts: MissingCounts,
+}
+
+impl<R: BufRead> Rows<R> {
+    /// new reads the header, if any, so an error reading it is returned here
+    pub fn new(
+        input: R,
+        delimiter: Delimiter,
+        with_header: bool,
+        columns: Option<Columns>,
+    ) -> Result<Self, SeriesError> {
+        let mut records = Records::new(input, delimiter);
+
+        let header = if with_header {
+            records
+                .next()
+                .transpose()?
+                .map(|names| names.iter().map(|n| n.trim().to_string()).collect())
+        } else {
+            None
+        };
+
+        Ok(Rows {
+            records,
+            header,
+            columns,
+            selected: None,
+            without: None,
+            missing: Missing::default(),
+            counts: MissingCounts::default(),
+        })
+    }
+
+    /// missing sets the policy for missing values, by default reading a row with one returns
+    /// SeriesError::Missing
+    pub fn missing(mut self, missing: Missing) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    /// without leaves a column out of every row, e.g. the label column of a matrix
+    pub fn without(mut self, col: usize) -> Self {
+        self.without = Some(col);
+        self
+    }
+
+    pub fn header(&self) -> Option<&[String]> {
+        self.header.as_deref()
+    }
+
+    /// the line number of the last row read
+    pub fn line(&self) -> usize {
+        self.records.line
+    }
+
+    /// the missing values seen so far, complete once the rows are exhausted
+    pub fn missing_counts(&self) -> &MissingCounts {
+        &self.counts
+    }
+
+    /// names returns the name of each selected column, or its index without a header. The
+    /// selection is only known once the first row has been read.
+    pub fn names(&self) -> Vec<String> {
+        match &self.selected {
+            Some(selected) => selected
+                .iter()
+                .map(|i| match &self.header {
+                    Some(h) if *i < h.len() => h[*i].to_string(),
+                    _ => i.to_string(),
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Rows<R> {
+    type Item = Result<Vec<f64>, SeriesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut row = match self.next_row().transpose()? {
+                Ok(row) => row,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if self.counts.apply(&mut row, self.missing) {
+                return Some(Ok(row));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Rows<R> {
+    /// next_labels returns the selected cells of the next row unparsed, e.g. class labels,
+    /// rather than as numbers. A row with a missing cell is an error when the policy is to
+    /// error, otherwise it is skipped.
+    pub fn next_labels(&mut self) -> Result<Option<Vec<String>>, SeriesError> {
+        loop {
+            let cols = match self.records.next() {
+                Some(cols) => cols?,
+                None => return Ok(None),
+            };
+            let line = self.records.line;
+
+            self.resolve(cols.len())?;
+
+            let mut row = vec![];
+            for i in self.selected.as_ref().unwrap() {
+                match cols.get(*i) {
+                    Some(cell) => row.push(cell.trim().to_string()),
+                    None => return Err(SeriesError::MissingColumn { column: *i, line }),
+                }
+            }
+
+            let missing = row.iter().filter(|c| is_missing(c)).count();
+
+            if missing == 0 {
+                return Ok(Some(row));
+            }
+
+            if self.missing == Missing::Error {
+                return Err(SeriesError::Missing { line });
+            }
+
+            self.counts.values += missing;
+            self.counts.rows += 1;
+        }
+    }
+
+    /// resolve resolves the column selection against the first row
+    fn resolve(&mut self, len: usize) -> Result<(), SeriesError> {
+        if self.selected.is_none() {
+            let selected = select(self.columns.as_ref(), self.header.as_deref(), len)?;
+            self.selected = Some(
+                selected
+                    .into_iter()
+                    .filter(|i| Some(*i) != self.without)
+                    .collect(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// next_row parses the next record, with missing values as NaN
+    fn next_row(&mut self) -> Result<Option<Vec<f64>>, SeriesError> {
+        let cols = match self.records.next() {
+            Some(cols) => cols?,
+            None => return Ok(None),
+        };
+        let line = self.records.line;
+        let missing = self.missing;
+
+        self.resolve(cols.len())?;
+
+        // without a selection rows may vary in length
+        if self.columns.is_none() {
+            return cols
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| Some(*i) != self.without)
+                .map(|(_, c)| parse_value(c, line, missing))
+                .collect::<Result<_, _>>()
+                .map(Some);
+        }
+
+        let mut row = vec![];
+        for i in self.selected.as_ref().unwrap() {
+            match cols.get(*i) {
+                Some(cell) => row.push(parse_value(cell, line, missing)?),
+                None => return Err(SeriesError::MissingColumn { column: *i, line }),
+            }
+        }
+
+        Ok(Some(row))
+    }
+}
+
+/// complete_rows returns only the rows without missing values, the rows it leaves out are
+/// counted as skipped. When the policy imputes the column mean or median, the rows are read
+/// into memory and imputed first.
+pub fn complete_rows<'a, R: BufRead + 'a>(
+    rows: &'a mut Rows<R>,
+) -> Box<dyn Iterator<Item = Result<Vec<f64>, SeriesError>> + 'a> {
+    let missing = rows.missing;
+
+    if missing == Missing::ImputeMean || missing == Missing::ImputeMedian {
+        let mut data: Vec<Vec<f64>> = match rows.by_ref().collect() {
+            Ok(data) => data,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+        impute(&mut data, missing);
+
+        // a column with no values at all is left NaN
+        let n = data.len();
+        data.retain(|row| row.iter().all(|x| !x.is_nan()));
+        rows.counts.rows += n - data.len();
+
+        return Box::new(data.into_iter().map(Ok));
+    }
+
+    Box::new(std::iter::from_fn(move || loop {
+        match rows.next()? {
+            Ok(row) if row.iter().any(|x| x.is_nan()) => rows.counts.rows += 1,
+            row => return Some(row),
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        let input = "a,\"b,c\",\"say \"\"hi\"\"\"\r\n\r\n1,\"2\n3\",4\n";
+        let records: Vec<Vec<String>> = Records::new(input.as_bytes(), Delimiter::Csv(','))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], vec!["a", "b,c", "say \"hi\""]);
+        assert_eq!(records[1], vec!["1", "2\n3", "4"]);
+
+        let input = "1\t\"2\"\n3 \t4\n";
+        let records: Vec<Vec<String>> = Records::new(input.as_bytes(), Delimiter::Tsv)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, vec![vec!["1", "\"2\""], vec!["3 ", "4"]]);
+
+        let input = "  1   2\t3\n";
+        let records: Vec<Vec<String>> = Records::new(input.as_bytes(), Delimiter::Whitespace)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, vec![vec!["1", "2", "3"]]);
+
+        let mut records = Records::new("1,\"2\n".as_bytes(), Delimiter::default());
+        assert!(matches!(
+            records.next(),
+            Some(Err(SeriesError::UnterminatedQuote { line: 1 }))
+        ));
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn test_rows() {
+        let input = "a,b,c\n1,2,3\n4,5,6\n";
+        let columns: Columns = "c,a".parse().unwrap();
+
+        let mut rows =
+            Rows::new(input.as_bytes(), Delimiter::default(), true, Some(columns)).unwrap();
+
+        assert_eq!(rows.next().unwrap().unwrap(), vec![3.0, 1.0]);
+        assert_eq!(rows.names(), vec!["c", "a"]);
+        assert_eq!(rows.next().unwrap().unwrap(), vec![6.0, 4.0]);
+        assert!(rows.next().is_none());
+    }
+
+    #[test]
+    fn test_complete_rows() {
+        let input = "1,2\n,3\n4,5\n";
+        let mut rows = Rows::new(input.as_bytes(), Delimiter::default(), false, None)
+            .unwrap()
+            .missing(Missing::SkipCell);
+
+        let complete: Vec<Vec<f64>> = complete_rows(&mut rows).collect::<Result<_, _>>().unwrap();
+        assert_eq!(complete, vec![vec![1.0, 2.0], vec![4.0, 5.0]]);
+        assert_eq!(
+            (rows.missing_counts().values, rows.missing_counts().rows),
+            (1, 1)
+        );
+    }
+}
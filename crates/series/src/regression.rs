@@ -0,0 +1,99 @@
+use crate::{quantile, OnlineStats, QuantileType};
+
+/// Regression holds the error metrics of a regression model's predictions. Residuals are
+/// actual - predicted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub n: usize,
+    pub mae: f64,
+    pub mse: f64,
+    pub rmse: f64,
+    /// the mean of |residual| / |actual| as a fraction, rows with an actual of 0 are left out,
+    /// NaN when every actual is 0
+    pub mape: f64,
+    /// 1 - SS_res / SS_tot, NaN for a constant actual
+    pub r2: f64,
+    /// 1 - var(residual) / var(actual), unlike r2 a constant bias is not penalized
+    pub explained_variance: f64,
+    pub median_absolute_error: f64,
+    /// (probability, residual) pairs
+    pub residuals: Vec<(f64, f64)>,
+}
+
+/// regression computes the metrics of (predicted, actual) pairs and the residual at each of the
+/// given probabilities, the input must be non empty
+pub fn regression(tuples: &[(f64, f64)], probabilities: &[f64]) -> Regression {
+    let mut actual = OnlineStats::default();
+    let mut residual = OnlineStats::default();
+    let mut residuals = vec![];
+    let mut absolute = vec![];
+    let (mut sum_abs, mut sum_sq) = (0.0, 0.0);
+    let (mut sum_pct, mut nonzero) = (0.0, 0);
+
+    for (p, a) in tuples {
+        let r = a - p;
+
+        actual.push(*a);
+        residual.push(r);
+        residuals.push(r);
+        absolute.push(r.abs());
+
+        sum_abs += r.abs();
+        sum_sq += r * r;
+
+        if *a != 0.0 {
+            sum_pct += (r / a).abs();
+            nonzero += 1;
+        }
+    }
+
+    let n = tuples.len() as f64;
+    let mse = sum_sq / n;
+    let ss_tot = actual.var() * n;
+
+    residuals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    absolute.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    Regression {
+        n: tuples.len(),
+        mae: sum_abs / n,
+        mse,
+        rmse: mse.sqrt(),
+        mape: sum_pct / nonzero as f64,
+        r2: if ss_tot == 0.0 {
+            f64::NAN
+        } else {
+            1.0 - sum_sq / ss_tot
+        },
+        explained_variance: if ss_tot == 0.0 {
+            f64::NAN
+        } else {
+            1.0 - residual.var() / actual.var()
+        },
+        median_absolute_error: quantile(&absolute, 0.5, QuantileType::default()),
+        residuals: probabilities
+            .iter()
+            .map(|p| (*p, quantile(&residuals, *p, QuantileType::default())))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_regression() {
+        // the r2_score example of scikit-learn, as (predicted, actual)
+        let tuples = vec![(2.5, 3.0), (0.0, -0.5), (2.0, 2.0), (8.0, 7.0)];
+        let r = regression(&tuples, &[0.0, 0.5, 1.0]);
+
+        assert_eq!((r.mae, r.mse), (0.5, 0.375));
+        assert_eq!(r.median_absolute_error, 0.5);
+        assert!((r.r2 - 0.948608).abs() < 1e-6);
+        assert!((r.explained_variance - 0.957173).abs() < 1e-6);
+        assert!((r.mape - 0.327380).abs() < 1e-6);
+        assert_eq!(r.residuals, vec![(0.0, -1.0), (0.5, -0.25), (1.0, 0.5)]);
+    }
+}
@@ -0,0 +1,111 @@
+/// RocPoint holds the counts at one threshold, a score at or above the threshold is predicted
+/// positive. The first point of a curve has an infinite threshold, nothing is predicted
+/// positive and the precision is taken as 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RocPoint {
+    pub threshold: f64,
+    pub tp: usize,
+    pub fp: usize,
+    pub tpr: f64,
+    pub fpr: f64,
+    pub precision: f64,
+}
+
+/// Roc holds the ROC and precision-recall curves of a binary prediction, with a point at every
+/// distinct score. The recall of the precision-recall curve is the tpr.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Roc {
+    pub points: Vec<RocPoint>,
+    pub positives: usize,
+    pub negatives: usize,
+    /// the area under the ROC curve, by the trapezoidal rule so tied scores count half
+    pub auc: f64,
+    /// the precision at each threshold weighted by the increase in recall, as in scikit-learn,
+    /// rather than the interpolated area under the precision-recall curve
+    pub average_precision: f64,
+}
+
+/// roc computes the exact curves of (score, actual) pairs, the AUC and average precision are
+/// NaN unless both classes are present
+pub fn roc(input: &[(f64, bool)]) -> Roc {
+    let mut sorted = input.to_vec();
+    sorted.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+    let positives = sorted.iter().filter(|(_, a)| *a).count();
+    let negatives = sorted.len() - positives;
+
+    let point = |threshold: f64, tp: usize, fp: usize| RocPoint {
+        threshold,
+        tp,
+        fp,
+        tpr: tp as f64 / positives as f64,
+        fpr: fp as f64 / negatives as f64,
+        precision: if tp + fp == 0 {
+            1.0
+        } else {
+            tp as f64 / (tp + fp) as f64
+        },
+    };
+
+    let mut points = vec![point(f64::INFINITY, 0, 0)];
+    let (mut tp, mut fp) = (0, 0);
+
+    for (index, (score, actual)) in sorted.iter().enumerate() {
+        if *actual {
+            tp += 1;
+        } else {
+            fp += 1;
+        }
+
+        // a point once every pair with this score is counted
+        if sorted.get(index + 1).map(|next| next.0) != Some(*score) {
+            points.push(point(*score, tp, fp));
+        }
+    }
+
+    let mut auc = 0.0;
+    let mut average_precision = 0.0;
+
+    for pair in points.windows(2) {
+        auc += (pair[1].fpr - pair[0].fpr) * (pair[1].tpr + pair[0].tpr) / 2.0;
+        average_precision += (pair[1].tpr - pair[0].tpr) * pair[1].precision;
+    }
+
+    if positives == 0 || negatives == 0 {
+        auc = f64::NAN;
+        average_precision = f64::NAN;
+    }
+
+    Roc {
+        points,
+        positives,
+        negatives,
+        auc,
+        average_precision,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_roc() {
+        // the roc_auc_score and average_precision_score example of scikit-learn
+        let input = vec![(0.1, false), (0.4, false), (0.35, true), (0.8, true)];
+        let r = roc(&input);
+
+        assert_eq!(r.points.len(), 5);
+        assert_eq!((r.positives, r.negatives), (2, 2));
+        assert!((r.auc - 0.75).abs() < 1e-12);
+        assert!((r.average_precision - 0.833333).abs() < 1e-6);
+
+        // a tie between the classes is half right
+        let r = roc(&[(0.5, true), (0.5, false)]);
+        assert_eq!(r.points.len(), 2);
+        assert_eq!(r.auc, 0.5);
+
+        assert!(roc(&[(0.5, true)]).auc.is_nan());
+    }
+}
@@ -0,0 +1,271 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::SeriesError;
+
+/// the first line of a serialized digest
+const MAGIC: &str = "tdigest";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// TDigest is a merging t-digest (Dunning and Ertl), a sketch of a distribution that
+/// estimates quantiles in bounded memory. Values are buffered and merged into at most about
+/// 'compression' centroids, which are smallest at the tails so extreme quantiles stay
+/// accurate. A larger compression is more accurate and uses more memory, 100 is typical.
+///
+/// Digests can be merged, so shards of the input may be sketched separately, and are
+/// serialized as text, see the Display and FromStr impls.
+#[derive(Debug, Clone)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<Centroid>,
+    count: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        TDigest::new(100.0)
+    }
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> Self {
+        TDigest {
+            compression: compression.max(1.0),
+            centroids: vec![],
+            buffer: vec![],
+            count: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// the number of values in the digest
+    pub fn count(&self) -> f64 {
+        self.count
+    }
+
+    pub fn push(&mut self, x: f64) {
+        self.add(Centroid {
+            mean: x,
+            weight: 1.0,
+        });
+    }
+
+    /// merge adds every value of another digest to this one
+    pub fn merge(&mut self, other: &TDigest) {
+        for c in other.centroids.iter().chain(&other.buffer) {
+            self.add(*c);
+        }
+    }
+
+    fn add(&mut self, c: Centroid) {
+        self.count += c.weight;
+        self.min = self.min.min(c.mean);
+        self.max = self.max.max(c.mean);
+        self.buffer.push(c);
+
+        if self.buffer.len() as f64 >= self.compression * 5.0 {
+            self.compress();
+        }
+    }
+
+    /// k is the scale function, centroids may only span one unit of k
+    fn k(&self, q: f64) -> f64 {
+        self.compression / (2.0 * PI) * (2.0 * q - 1.0).asin()
+    }
+
+    fn k_inverse(&self, k: f64) -> f64 {
+        ((2.0 * PI * k / self.compression).sin() + 1.0) / 2.0
+    }
+
+    /// compress merges the buffered values into the centroids
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let mut all = std::mem::take(&mut self.buffer);
+        all.append(&mut self.centroids);
+        all.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap());
+
+        let total = self.count;
+        let mut merged = vec![];
+        let mut current = all[0];
+        let mut so_far = 0.0;
+        let mut limit = self.k_inverse(self.k(0.0) + 1.0);
+
+        for next in all.into_iter().skip(1) {
+            let q = (so_far + current.weight + next.weight) / total;
+
+            if q <= limit {
+                // weighted mean, written so equal means stay exact
+                current.weight += next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / current.weight;
+            } else {
+                so_far += current.weight;
+                merged.push(current);
+                limit = self.k_inverse(self.k(so_far / total) + 1.0);
+                current = next;
+            }
+        }
+
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// quantile estimates the p quantile, NaN for an empty digest. Between the centers of two
+    /// centroids the estimate is interpolated linearly, and towards min and max at the ends.
+    pub fn quantile(&mut self, p: f64) -> f64 {
+        self.compress();
+
+        if self.centroids.is_empty() {
+            return f64::NAN;
+        }
+
+        if p <= 0.0 {
+            return self.min;
+        }
+
+        if p >= 1.0 {
+            return self.max;
+        }
+
+        let index = p * self.count;
+        let first = self.centroids[0];
+
+        if index < first.weight / 2.0 {
+            return self.min + (first.mean - self.min) * index / (first.weight / 2.0);
+        }
+
+        // the cumulative weight at the center of the current centroid
+        let mut center = first.weight / 2.0;
+
+        for pair in self.centroids.windows(2) {
+            let next = center + (pair[0].weight + pair[1].weight) / 2.0;
+
+            if index < next {
+                let t = (index - center) / (next - center);
+                return pair[0].mean + t * (pair[1].mean - pair[0].mean);
+            }
+
+            center = next;
+        }
+
+        let last = self.centroids[self.centroids.len() - 1];
+        let t = ((index - center) / (last.weight / 2.0)).min(1.0);
+
+        last.mean + t * (self.max - last.mean)
+    }
+}
+
+/// a digest is written as a header line, "tdigest,compression,count,min,max", followed by a
+/// "mean,weight" line for each centroid
+impl fmt::Display for TDigest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut digest = self.clone();
+        digest.compress();
+
+        writeln!(
+            f,
+            "{},{},{},{},{}",
+            MAGIC, digest.compression, digest.count, digest.min, digest.max
+        )?;
+
+        for c in &digest.centroids {
+            writeln!(f, "{},{}", c.mean, c.weight)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for TDigest {
+    type Err = SeriesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |line: usize| SeriesError::Sketch { line };
+
+        let mut lines = s.lines().enumerate();
+
+        let header: Vec<&str> = match lines.next() {
+            Some((_, line)) => line.split(',').collect(),
+            None => return Err(SeriesError::Empty),
+        };
+
+        if header.len() != 5 || header[0] != MAGIC {
+            return Err(invalid(1));
+        }
+
+        let values: Vec<f64> = header[1..]
+            .iter()
+            .map(|v| v.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid(1))?;
+
+        let mut digest = TDigest::new(values[0]);
+        digest.min = values[2];
+        digest.max = values[3];
+
+        for (index, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (mean, weight) = line.split_once(',').ok_or_else(|| invalid(index + 1))?;
+
+            digest.centroids.push(Centroid {
+                mean: mean.trim().parse().map_err(|_| invalid(index + 1))?,
+                weight: weight.trim().parse().map_err(|_| invalid(index + 1))?,
+            });
+        }
+
+        digest.count = digest.centroids.iter().map(|c| c.weight).sum();
+        if digest.count != values[1] {
+            return Err(invalid(1));
+        }
+
+        Ok(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_tdigest() {
+        let mut digest = TDigest::new(100.0);
+        let mut shards = [TDigest::new(100.0), TDigest::new(100.0)];
+
+        // a shuffled 0..10000
+        for i in 0..10000 {
+            let x = ((i * 7919) % 10000) as f64;
+            digest.push(x);
+            shards[i % 2].push(x);
+        }
+
+        for (p, expected) in [(0.01, 99.99), (0.5, 4999.5), (0.99, 9899.01)] {
+            assert!((digest.quantile(p) - expected).abs() < 10.0);
+        }
+
+        assert_eq!(digest.quantile(0.0), 0.0);
+        assert_eq!(digest.quantile(1.0), 9999.0);
+
+        let mut merged: TDigest = shards[0].to_string().parse().unwrap();
+        merged.merge(&shards[1].to_string().parse().unwrap());
+
+        assert_eq!(merged.count(), 10000.0);
+        assert!((merged.quantile(0.5) - 4999.5).abs() < 10.0);
+        assert!("tdigest,100,3,0,1\n0.5,2\n".parse::<TDigest>().is_err());
+    }
+}
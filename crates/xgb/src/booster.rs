@@ -0,0 +1,377 @@
+use crate::XgbError;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_int;
+use std::ptr;
+use std::slice;
+
+// A thin binding over the XGBoost C API. The xgboost crate only sets the parameters it has a
+// builder for, and its handles are private, so the booster and its datasets are wrapped here
+// and every parameter is passed to XGBoost as a string.
+
+/// check turns the return value of an XGBoost call into a result, with XGBoost's last error
+fn check(ret: c_int) -> Result<(), XgbError> {
+    if ret == 0 {
+        return Ok(());
+    }
+
+    let e = unsafe { CStr::from_ptr(xgboost_sys::XGBGetLastError()) };
+    Err(XgbError::Xgboost(e.to_string_lossy().into_owned()))
+}
+
+fn c_string(s: &str) -> Result<CString, XgbError> {
+    CString::new(s).map_err(|_| XgbError::Param(format!("{:?} contains a nul byte", s)))
+}
+
+/// parse_eval reads the metric and score of an evaluation of the dataset named eval, e.g.
+/// "[0]\teval-rmse:0.5", the metric name may hold a '-', as in "ndcg@5-"
+fn parse_eval(out: &str) -> Result<(String, f32), XgbError> {
+    out.split('\t')
+        .nth(1)
+        .and_then(|part| part.strip_prefix("eval-"))
+        .and_then(|part| part.rsplit_once(':'))
+        .and_then(|(metric, score)| Some((metric.to_string(), score.parse().ok()?)))
+        .ok_or_else(|| XgbError::Xgboost(format!("unexpected evaluation {}", out)))
+}
+
+/// DMatrix is a dataset of XGBoost, the features of each row with its label, and the group
+/// sizes of a ranking
+pub struct DMatrix {
+    handle: xgboost_sys::DMatrixHandle,
+}
+
+impl DMatrix {
+    /// from_csr builds a sparse matrix, the values of row i are at indptr[i]..indptr[i + 1]
+    pub(crate) fn from_csr(
+        indptr: &[usize],
+        indices: &[u32],
+        data: &[f32],
+        ncols: usize,
+    ) -> Result<DMatrix, XgbError> {
+        let mut handle = ptr::null_mut();
+        check(unsafe {
+            xgboost_sys::XGDMatrixCreateFromCSREx(
+                indptr.as_ptr(),
+                indices.as_ptr(),
+                data.as_ptr(),
+                indptr.len(),
+                data.len(),
+                ncols,
+                &mut handle,
+            )
+        })?;
+
+        Ok(DMatrix { handle })
+    }
+
+    pub(crate) fn set_labels(&mut self, labels: &[f32]) -> Result<(), XgbError> {
+        let field = c_string("label")?;
+        check(unsafe {
+            xgboost_sys::XGDMatrixSetFloatInfo(
+                self.handle,
+                field.as_ptr(),
+                labels.as_ptr(),
+                labels.len() as u64,
+            )
+        })
+    }
+
+    pub(crate) fn labels(&self) -> Result<Vec<f32>, XgbError> {
+        let field = c_string("label")?;
+        let mut len = 0;
+        let mut labels = ptr::null();
+        check(unsafe {
+            xgboost_sys::XGDMatrixGetFloatInfo(self.handle, field.as_ptr(), &mut len, &mut labels)
+        })?;
+
+        if len == 0 {
+            return Ok(vec![]);
+        }
+
+        Ok(unsafe { slice::from_raw_parts(labels, len as usize) }.to_vec())
+    }
+
+    pub(crate) fn set_groups(&mut self, groups: &[u32]) -> Result<(), XgbError> {
+        check(unsafe {
+            xgboost_sys::XGDMatrixSetGroup(self.handle, groups.as_ptr(), groups.len() as u64)
+        })
+    }
+}
+
+impl Drop for DMatrix {
+    fn drop(&mut self) {
+        unsafe {
+            xgboost_sys::XGDMatrixFree(self.handle);
+        }
+    }
+}
+
+/// Booster is a model of XGBoost, trained a round at a time
+pub(crate) struct Booster {
+    handle: xgboost_sys::BoosterHandle,
+}
+
+impl Booster {
+    /// new creates a booster, the datasets it is trained and evaluated on are cached by it
+    pub(crate) fn new(cached: &[&DMatrix]) -> Result<Booster, XgbError> {
+        let handles: Vec<_> = cached.iter().map(|dmat| dmat.handle).collect();
+        let mut handle = ptr::null_mut();
+        check(unsafe {
+            xgboost_sys::XGBoosterCreate(handles.as_ptr(), handles.len() as u64, &mut handle)
+        })?;
+
+        Ok(Booster { handle })
+    }
+
+    pub(crate) fn load(path: &str) -> Result<Booster, XgbError> {
+        let path = c_string(path)?;
+        let bst = Booster::new(&[])?;
+        check(unsafe { xgboost_sys::XGBoosterLoadModel(bst.handle, path.as_ptr()) })?;
+        Ok(bst)
+    }
+
+    pub(crate) fn load_buffer(model: &[u8]) -> Result<Booster, XgbError> {
+        let bst = Booster::new(&[])?;
+        check(unsafe {
+            xgboost_sys::XGBoosterLoadModelFromBuffer(
+                bst.handle,
+                model.as_ptr() as *const _,
+                model.len() as u64,
+            )
+        })?;
+        Ok(bst)
+    }
+
+    pub(crate) fn save(&self, path: &str) -> Result<(), XgbError> {
+        let path = c_string(path)?;
+        check(unsafe { xgboost_sys::XGBoosterSaveModel(self.handle, path.as_ptr()) })
+    }
+
+    /// set_param sets a parameter by its XGBoost name, XGBoost checks the value once training
+    /// starts
+    pub(crate) fn set_param(&mut self, name: &str, value: &str) -> Result<(), XgbError> {
+        let (name, value) = (c_string(name)?, c_string(value)?);
+        check(unsafe { xgboost_sys::XGBoosterSetParam(self.handle, name.as_ptr(), value.as_ptr()) })
+    }
+
+    /// update trains a round with the objective of the booster
+    pub(crate) fn update(&mut self, dtrain: &DMatrix, round: i32) -> Result<(), XgbError> {
+        check(unsafe { xgboost_sys::XGBoosterUpdateOneIter(self.handle, round, dtrain.handle) })
+    }
+
+    /// boost trains a round with the gradient and hessian of a custom objective
+    pub(crate) fn boost(
+        &mut self,
+        dtrain: &DMatrix,
+        grad: &mut [f32],
+        hess: &mut [f32],
+    ) -> Result<(), XgbError> {
+        assert_eq!(grad.len(), hess.len());
+        check(unsafe {
+            xgboost_sys::XGBoosterBoostOneIter(
+                self.handle,
+                dtrain.handle,
+                grad.as_mut_ptr(),
+                hess.as_mut_ptr(),
+                grad.len() as u64,
+            )
+        })
+    }
+
+    /// evaluate returns the name and value of the first metric of the booster on a dataset
+    pub(crate) fn evaluate(&self, dmat: &DMatrix) -> Result<(String, f32), XgbError> {
+        let name = c_string("eval")?;
+        let mut handles = [dmat.handle];
+        let mut names = [name.as_ptr()];
+        let mut out = ptr::null();
+        check(unsafe {
+            xgboost_sys::XGBoosterEvalOneIter(
+                self.handle,
+                0,
+                handles.as_mut_ptr(),
+                names.as_mut_ptr(),
+                1,
+                &mut out,
+            )
+        })?;
+
+        parse_eval(&unsafe { CStr::from_ptr(out) }.to_string_lossy())
+    }
+
+    pub(crate) fn predict(&self, dmat: &DMatrix) -> Result<Vec<f32>, XgbError> {
+        let mut len = 0;
+        let mut out = ptr::null();
+        check(unsafe {
+            xgboost_sys::XGBoosterPredict(self.handle, dmat.handle, 0, 0, &mut len, &mut out)
+        })?;
+
+        if len == 0 {
+            return Ok(vec![]);
+        }
+
+        Ok(unsafe { slice::from_raw_parts(out, len as usize) }.to_vec())
+    }
+
+    pub(crate) fn set_attribute(&mut self, key: &str, value: &str) -> Result<(), XgbError> {
+        let (key, value) = (c_string(key)?, c_string(value)?);
+        check(unsafe { xgboost_sys::XGBoosterSetAttr(self.handle, key.as_ptr(), value.as_ptr()) })
+    }
+
+    pub(crate) fn attribute(&self, key: &str) -> Result<Option<String>, XgbError> {
+        let key = c_string(key)?;
+        let mut out = ptr::null();
+        let mut found = 0;
+        check(unsafe {
+            xgboost_sys::XGBoosterGetAttr(self.handle, key.as_ptr(), &mut out, &mut found)
+        })?;
+
+        if found == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            unsafe { CStr::from_ptr(out) }
+                .to_string_lossy()
+                .into_owned(),
+        ))
+    }
+
+    /// dump returns the text dump of the trees, one after another, with the gain and cover of
+    /// each split
+    pub(crate) fn dump(&self) -> Result<String, XgbError> {
+        let (fmap, format) = (c_string("")?, c_string("text")?);
+        let mut len = 0;
+        let mut out = ptr::null_mut();
+        check(unsafe {
+            xgboost_sys::XGBoosterDumpModelEx(
+                self.handle,
+                fmap.as_ptr(),
+                1,
+                format.as_ptr(),
+                &mut len,
+                &mut out,
+            )
+        })?;
+
+        if len == 0 {
+            return Ok(String::new());
+        }
+
+        let trees: Vec<String> = unsafe { slice::from_raw_parts(out, len as usize) }
+            .iter()
+            .map(|tree| {
+                unsafe { CStr::from_ptr(*tree) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        Ok(trees.join("\n"))
+    }
+}
+
+impl Drop for Booster {
+    fn drop(&mut self) {
+        unsafe {
+            xgboost_sys::XGBoosterFree(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_eval() {
+        assert_eq!(
+            parse_eval("[0]\teval-rmse:0.5"),
+            Ok(("rmse".to_string(), 0.5))
+        );
+        assert_eq!(
+            parse_eval("[12]\teval-ndcg@5-:0.75"),
+            Ok(("ndcg@5-".to_string(), 0.75))
+        );
+
+        for out in [
+            "",
+            "[0]",
+            "[0]\ttrain-rmse:0.5",
+            "[0]\teval-rmse",
+            "[0]\teval-rmse:x",
+        ] {
+            assert!(
+                matches!(parse_eval(out), Err(XgbError::Xgboost(_))),
+                "{:?}",
+                out
+            );
+        }
+    }
+
+    #[test]
+    fn test_c_string() {
+        assert_eq!(c_string("eta").unwrap().as_bytes(), b"eta");
+        assert_eq!(
+            c_string("e\0ta"),
+            Err(XgbError::Param(
+                "\"e\\0ta\" contains a nul byte".to_string()
+            ))
+        );
+    }
+
+    // the tests below call into XGBoost
+
+    #[test]
+    fn test_dmatrix() {
+        // row 1 leaves out column 0, as a missing value
+        let indptr = [0, 2, 3];
+        let mut dmat = DMatrix::from_csr(&indptr, &[0, 1, 1], &[1.0, 2.0, 3.0], 2).unwrap();
+        assert_eq!(dmat.labels(), Ok(vec![]));
+
+        dmat.set_labels(&[0.0, 1.0]).unwrap();
+        assert_eq!(dmat.labels(), Ok(vec![0.0, 1.0]));
+        dmat.set_groups(&[2]).unwrap();
+    }
+
+    #[test]
+    fn test_booster() {
+        let indptr: Vec<usize> = (0..=20).collect();
+        let indices = vec![0; 20];
+        let data: Vec<f32> = (0..20).map(|i| i as f32).collect();
+        let mut dtrain = DMatrix::from_csr(&indptr, &indices, &data, 1).unwrap();
+        dtrain.set_labels(&data).unwrap();
+
+        let mut bst = Booster::new(&[&dtrain]).unwrap();
+        for (name, value) in [("silent", "1"), ("objective", "reg:linear"), ("eta", "1")] {
+            bst.set_param(name, value).unwrap();
+        }
+        bst.set_param("objective", "reg:nope").unwrap();
+        assert!(matches!(bst.update(&dtrain, 0), Err(XgbError::Xgboost(_))));
+
+        let mut bst = Booster::new(&[&dtrain]).unwrap();
+        for (name, value) in [("silent", "1"), ("objective", "reg:linear"), ("eta", "1")] {
+            bst.set_param(name, value).unwrap();
+        }
+        for round in 0..5 {
+            bst.update(&dtrain, round).unwrap();
+        }
+
+        let (metric, score) = bst.evaluate(&dtrain).unwrap();
+        assert_eq!(metric, "rmse");
+        assert!(score < 1.0, "{}", score);
+
+        let preds = bst.predict(&dtrain).unwrap();
+        assert_eq!(preds.len(), 20);
+        assert!((preds[10] - 10.0).abs() < 1.0, "{:?}", preds);
+
+        assert_eq!(bst.attribute("objective"), Ok(None));
+        bst.set_attribute("objective", "reg:squarederror").unwrap();
+        assert_eq!(
+            bst.attribute("objective"),
+            Ok(Some("reg:squarederror".to_string()))
+        );
+
+        let dump = bst.dump().unwrap();
+        assert!(dump.starts_with("0:[f0<"), "{}", dump);
+        assert!(dump.contains("gain="), "{}", dump);
+    }
+}
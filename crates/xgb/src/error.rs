@@ -0,0 +1,32 @@
+use std::fmt;
+
+/// XgbError is returned by the training, prediction and model functions of this crate
+#[derive(Debug, Clone, PartialEq)]
+pub enum XgbError {
+    /// an error reported by XGBoost
+    Xgboost(String),
+    /// a parameter that can't be passed to XGBoost, such as one holding a nul byte
+    Param(String),
+    /// an objective that is not one of train's
+    Objective(String),
+    /// a line of a model dump that can't be parsed
+    Dump(String),
+}
+
+impl fmt::Display for XgbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XgbError::Xgboost(e) => write!(f, "xgboost: {}", e),
+            XgbError::Param(e) => write!(f, "{}", e),
+            XgbError::Objective(objective) => write!(
+                f,
+                "invalid objective {}, expected one of {}",
+                objective,
+                crate::OBJECTIVES.join(", ")
+            ),
+            XgbError::Dump(line) => write!(f, "invalid model dump line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for XgbError {}
@@ -1,39 +1,166 @@
 use std::collections::HashMap;
-use xgboost::{parameters, Booster, DMatrix};
-
-pub fn to_xgboost_dataset(xdata: &[Vec<f64>], ydata: Option<Vec<f32>>) -> DMatrix {
-    let rows = xdata.len();
-    let mut xdata2 = vec![];
+use std::fmt;
+use std::str::FromStr;
+
+mod booster;
+mod error;
+use booster::Booster;
+pub use booster::DMatrix;
+pub use error::XgbError;
+
+/// OBJECTIVES are the objectives of train, by their xgboost names
+pub const OBJECTIVES: [&str; 12] = [
+    "binary:logistic",
+    "multi:softmax",
+    "multi:softprob",
+    "reg:squarederror",
+    "reg:logistic",
+    "reg:pseudohubererror",
+    "reg:gamma",
+    "reg:tweedie",
+    "count:poisson",
+    "survival:cox",
+    "rank:pairwise",
+    "rank:ndcg",
+];
+
+/// TreeMethod is the tree construction algorithm. Auto lets xgboost pick exact or approx by
+/// the size of the input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreeMethod {
+    Auto,
+    Exact,
+    Approx,
+    Hist,
+}
 
-    for row in xdata {
-        for item in row {
-            xdata2.push(*item as f32);
+impl FromStr for TreeMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(TreeMethod::Auto),
+            "exact" => Ok(TreeMethod::Exact),
+            "approx" => Ok(TreeMethod::Approx),
+            "hist" => Ok(TreeMethod::Hist),
+            _ => Err(format!(
+                "invalid tree method {}, expected auto, exact, approx or hist",
+                s
+            )),
         }
     }
+}
 
-    match DMatrix::from_dense(&xdata2, rows) {
-        Ok(mut x) => {
-            if let Some(y) = ydata {
-                let _ = x.set_labels(&y);
-            }
-            x
+impl fmt::Display for TreeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeMethod::Auto => write!(f, "auto"),
+            TreeMethod::Exact => write!(f, "exact"),
+            TreeMethod::Approx => write!(f, "approx"),
+            TreeMethod::Hist => write!(f, "hist"),
         }
-        Err(e) => {
-            eprintln!("{}", e);
-            std::process::exit(1);
+    }
+}
+
+/// Params are the booster and training parameters of train, the defaults are xgboost's
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    pub max_depth: u32,
+    pub eta: f32,
+    pub subsample: f32,
+    pub colsample_bytree: f32,
+    pub colsample_bylevel: f32,
+    pub min_child_weight: f32,
+    pub gamma: f32,
+    pub lambda: f32,
+    pub alpha: f32,
+    pub max_delta_step: f32,
+    pub scale_pos_weight: f32,
+    pub tree_method: TreeMethod,
+    /// the most bins of a feature, for the hist tree method
+    pub max_bin: u32,
+    pub base_score: f32,
+    pub seed: u64,
+    /// the variance power of reg:tweedie, between 1 and 2, xgboost's default is 1.5
+    pub tweedie_variance_power: Option<f32>,
+    /// the slope of reg:pseudohubererror, the error beyond which the loss is linear, 1 by
+    /// default
+    pub huber_slope: Option<f32>,
+    pub rounds: u32,
+    /// the metric reported and early stopped on, xgboost picks one for the objective by default
+    pub eval_metric: Option<Metric>,
+    /// stop when the validation metric hasn't improved for this many rounds
+    pub early_stopping_rounds: Option<u32>,
+    /// the number of threads of each booster, all cores by default
+    pub threads: Option<u32>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            max_depth: 6,
+            eta: 0.3,
+            subsample: 1.0,
+            colsample_bytree: 1.0,
+            colsample_bylevel: 1.0,
+            min_child_weight: 1.0,
+            gamma: 0.0,
+            lambda: 1.0,
+            alpha: 0.0,
+            max_delta_step: 0.0,
+            scale_pos_weight: 1.0,
+            tree_method: TreeMethod::Auto,
+            max_bin: 256,
+            base_score: 0.5,
+            seed: 0,
+            tweedie_variance_power: None,
+            huber_slope: None,
+            rounds: 10,
+            eval_metric: None,
+            early_stopping_rounds: None,
+            threads: None,
         }
     }
 }
 
-pub fn dump_model(model: &[u8], dump_model: bool, typ: &str) {
-    if dump_model {
-        let bst = Booster::load_buffer(model).unwrap();
-        println!("{}", bst.dump_model(true, None).unwrap());
-    } else {
-        let bst = Booster::load_buffer(model).unwrap();
-        let model = bst.dump_model(true, None).unwrap();
-        importance(model, typ);
+/// to_xgboost_dataset builds a sparse matrix where NaN values are left out, xgboost then
+/// treats them as missing. A dense matrix would treat every 0 as missing instead.
+pub fn to_xgboost_dataset(
+    xdata: &[Vec<f64>],
+    ydata: Option<Vec<f32>>,
+) -> Result<DMatrix, XgbError> {
+    let ncols = xdata.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut indptr = vec![0];
+    let mut indices = vec![];
+    let mut values = vec![];
+
+    for row in xdata {
+        for (index, item) in row.iter().enumerate() {
+            if !item.is_nan() {
+                indices.push(index as u32);
+                values.push(*item as f32);
+            }
+        }
+
+        indptr.push(values.len());
     }
+
+    let mut x = DMatrix::from_csr(&indptr, &indices, &values, ncols)?;
+    if let Some(y) = ydata {
+        x.set_labels(&y)?;
+    }
+
+    Ok(x)
+}
+
+/// set_groups sets the number of rows of each group of a ranking, in row order
+pub fn set_groups(dmat: &mut DMatrix, groups: &[u32]) -> Result<(), XgbError> {
+    dmat.set_groups(groups)
+}
+
+/// dump_model returns the text dump of a model, with the gain and cover of each split
+pub fn dump_model(model: &[u8]) -> Result<String, XgbError> {
+    Booster::load_buffer(model)?.dump()
 }
 
 #[derive(Default, Debug)]
@@ -43,7 +170,7 @@ struct XgboostNode {
     cover: f32,
 }
 
-fn parse_node(node_str: &str) -> XgboostNode {
+fn parse_node(node_str: &str) -> Result<XgboostNode, XgbError> {
     let mut name = String::new();
 
     let mut in_name = false;
@@ -89,10 +216,7 @@ fn parse_node(node_str: &str) -> XgboostNode {
                         val = String::new();
                         key_name = String::new();
                     }
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        std::process::exit(1);
-                    }
+                    Err(_) => return Err(XgbError::Dump(node_str.to_string())),
                 }
             }
 
@@ -117,16 +241,15 @@ fn parse_node(node_str: &str) -> XgboostNode {
                 node.cover = f;
             }
         }
-        Err(e) => {
-            eprintln!("{}", e);
-            std::process::exit(1);
-        }
+        Err(_) => return Err(XgbError::Dump(node_str.to_string())),
     }
 
-    node
+    Ok(node)
 }
 
-pub fn importance(model_dump: String, typ: &str) {
+/// importance returns the share of the total gain, cover or split count (freq) of each
+/// feature, highest first
+pub fn importance(model_dump: &str, typ: &str) -> Result<Vec<(String, f32)>, XgbError> {
     let mut gain_map = HashMap::new();
     let mut cover_map = HashMap::new();
     let mut freq_map = HashMap::new();
@@ -144,7 +267,7 @@ pub fn importance(model_dump: String, typ: &str) {
             continue;
         }
 
-        let node = parse_node(line);
+        let node = parse_node(line)?;
 
         total_freq += 1;
 
@@ -171,96 +294,456 @@ pub fn importance(model_dump: String, typ: &str) {
         }
     }
 
-    if typ == "gain" {
-        let mut list = vec![];
-        for (k, v) in gain_map {
-            list.push((k, v / total_gain));
-        }
-        list.sort_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap());
+    let mut list: Vec<(String, f32)> = match typ {
+        "gain" => gain_map
+            .into_iter()
+            .map(|(k, v)| (k, v / total_gain))
+            .collect(),
+        "cover" => cover_map
+            .into_iter()
+            .map(|(k, v)| (k, v / total_cover))
+            .collect(),
+        "freq" => freq_map
+            .into_iter()
+            .map(|(k, v)| (k, v as f32 / total_freq as f32))
+            .collect(),
+        _ => vec![],
+    };
+
+    list.sort_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap());
+
+    Ok(list)
+}
+
+pub fn predict(model: &str, test_set: &DMatrix) -> Result<Vec<f32>, XgbError> {
+    Booster::load(model)?.predict(test_set)
+}
 
-        for (name, val) in list {
-            println!("{} = {}", name, val);
+/// attribute returns an attribute saved with a model by train, "objective" or "labels"
+pub fn attribute(model: &str, key: &str) -> Result<Option<String>, XgbError> {
+    Booster::load(model)?.attribute(key)
+}
+
+/// Metric is an evaluation metric of train, by its xgboost name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Rmse,
+    Mae,
+    Logloss,
+    Error,
+    Merror,
+    Mlogloss,
+    Auc,
+    Ndcg,
+    Map,
+}
+
+/// higher_is_better is true for the metrics that grow as the model improves, by the name
+/// xgboost reports them with, e.g. "auc" or "ndcg@5"
+pub fn higher_is_better(metric: &str) -> bool {
+    ["auc", "ndcg", "map"].iter().any(|m| metric.starts_with(m))
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rmse" => Ok(Metric::Rmse),
+            "mae" => Ok(Metric::Mae),
+            "logloss" => Ok(Metric::Logloss),
+            "error" => Ok(Metric::Error),
+            "merror" => Ok(Metric::Merror),
+            "mlogloss" => Ok(Metric::Mlogloss),
+            "auc" => Ok(Metric::Auc),
+            "ndcg" => Ok(Metric::Ndcg),
+            "map" => Ok(Metric::Map),
+            _ => Err(format!(
+                "invalid metric {}, expected rmse, mae, logloss, error, merror, mlogloss, auc, \
+                 ndcg or map",
+                s
+            )),
         }
     }
+}
 
-    if typ == "cover" {
-        let mut list = vec![];
-        for (k, v) in cover_map {
-            list.push((k, v / total_cover));
-        }
-        list.sort_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap());
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Metric::Rmse => "rmse",
+            Metric::Mae => "mae",
+            Metric::Logloss => "logloss",
+            Metric::Error => "error",
+            Metric::Merror => "merror",
+            Metric::Mlogloss => "mlogloss",
+            Metric::Auc => "auc",
+            Metric::Ndcg => "ndcg",
+            Metric::Map => "map",
+        };
+        write!(f, "{}", name)
+    }
+}
 
-        for (name, val) in list {
-            println!("{} = {}", name, val);
-        }
+/// History holds the metric of each round of train, on the training set and on the
+/// validation set when there is one. The best iteration is the round with the best
+/// validation score.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    pub metric: String,
+    pub train: Vec<f32>,
+    pub valid: Vec<f32>,
+    pub best_iteration: Option<usize>,
+}
+
+/// booster_params returns the parameters of a booster by their xgboost names
+fn booster_params(
+    objective: &str,
+    nclasses: u32,
+    params: &Params,
+) -> Result<Vec<(&'static str, String)>, XgbError> {
+    // the metric defaults to reg:linear's rmse, which would early stop on the wrong loss
+    if objective == "reg:pseudohubererror"
+        && params.early_stopping_rounds.is_some()
+        && params.eval_metric.is_none()
+    {
+        return Err(XgbError::Param(
+            "early stopping reg:pseudohubererror needs an eval metric, its default is rmse"
+                .to_string(),
+        ));
     }
 
-    if typ == "freq" {
-        let mut list = vec![];
-        for (k, v) in freq_map {
-            list.push((k, v / total_freq));
-        }
-        list.sort_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap());
+    let objective = match objective {
+        // the bundled xgboost predates the rename of reg:linear to reg:squarederror, and
+        // reg:pseudohubererror, which is boosted with its gradient on top of reg:linear
+        "reg:squarederror" | "reg:linear" | "reg:pseudohubererror" => "reg:linear",
+        objective if OBJECTIVES.contains(&objective) => objective,
+        _ => return Err(XgbError::Objective(objective.to_string())),
+    };
 
-        for (name, val) in list {
-            println!("{} = {}", name, val);
-        }
+    let mut pairs = vec![
+        ("booster", "gbtree".to_string()),
+        ("silent", "1".to_string()),
+        ("objective", objective.to_string()),
+        ("base_score", params.base_score.to_string()),
+        ("seed", params.seed.to_string()),
+        ("max_depth", params.max_depth.to_string()),
+        ("eta", params.eta.to_string()),
+        ("subsample", params.subsample.to_string()),
+        ("colsample_bytree", params.colsample_bytree.to_string()),
+        ("colsample_bylevel", params.colsample_bylevel.to_string()),
+        ("min_child_weight", params.min_child_weight.to_string()),
+        ("gamma", params.gamma.to_string()),
+        ("lambda", params.lambda.to_string()),
+        ("alpha", params.alpha.to_string()),
+        ("max_delta_step", params.max_delta_step.to_string()),
+        ("scale_pos_weight", params.scale_pos_weight.to_string()),
+        ("tree_method", params.tree_method.to_string()),
+        ("max_bin", params.max_bin.to_string()),
+    ];
+
+    if objective.starts_with("multi:") {
+        pairs.push(("num_class", nclasses.to_string()));
+    }
+    if let (Some(power), "reg:tweedie") = (params.tweedie_variance_power, objective) {
+        pairs.push(("tweedie_variance_power", power.to_string()));
     }
+    if let Some(metric) = params.eval_metric {
+        pairs.push(("eval_metric", metric.to_string()));
+    }
+    if let Some(threads) = params.threads {
+        pairs.push(("nthread", threads.to_string()));
+    }
+
+    Ok(pairs)
 }
 
-pub fn predict(model: &str, test_set: &DMatrix) -> Vec<f32> {
-    let bst = Booster::load(model).unwrap();
-    bst.predict(test_set).unwrap()
+/// new_booster creates a booster with the parameters of train, caching the datasets it is
+/// trained and evaluated on
+fn new_booster(
+    cached: &[&DMatrix],
+    objective: &str,
+    nclasses: u32,
+    params: &Params,
+) -> Result<Booster, XgbError> {
+    let pairs = booster_params(objective, nclasses, params)?;
+    let mut bst = Booster::new(cached)?;
+
+    for (name, value) in pairs {
+        bst.set_param(name, &value)?;
+    }
+
+    Ok(bst)
 }
 
+/// pseudo_huber returns the gradient and hessian of the pseudo-Huber loss of each prediction,
+/// slope^2 * (sqrt(1 + (error / slope)^2) - 1), as xgboost computes reg:pseudohubererror
+fn pseudo_huber(predictions: &[f32], labels: &[f32], slope: f32) -> (Vec<f32>, Vec<f32>) {
+    predictions
+        .iter()
+        .zip(labels)
+        .map(|(p, y)| {
+            let z = p - y;
+            let scale = 1.0 + (z / slope).powi(2);
+            (z / scale.sqrt(), 1.0 / (scale * scale.sqrt()))
+        })
+        .unzip()
+}
+
+/// boost trains a booster for a round, with the gradient of reg:pseudohubererror computed
+/// here, and with xgboost's objective otherwise. The gradient needs the predictions of the
+/// whole training set, so each round of reg:pseudohubererror costs a predict as well.
+fn boost(
+    bst: &mut Booster,
+    dtrain: &DMatrix,
+    round: usize,
+    objective: &str,
+    params: &Params,
+) -> Result<(), XgbError> {
+    if objective != "reg:pseudohubererror" {
+        return bst.update(dtrain, round as i32);
+    }
+
+    let predictions = bst.predict(dtrain)?;
+    let labels = dtrain.labels()?;
+    let (mut grad, mut hess) =
+        pseudo_huber(&predictions, &labels, params.huber_slope.unwrap_or(1.0));
+
+    bst.boost(dtrain, &mut grad, &mut hess)
+}
+
+/// train trains a model and saves it to output, printing the metric of each round to stderr.
+/// The objective, and the class labels of a classifier one per line, are saved in the model
+/// as attributes, and so are the best iteration and its score with a validation set. With
+/// early stopping the model is saved at each new best round, so the saved model stops at the
+/// best iteration.
 pub fn train(
     training_set: &DMatrix,
+    valid: Option<&DMatrix>,
     objective: &str,
     nclasses: u32,
-    depth: u32,
-    eta: f32,
-    rounds: u32,
+    params: &Params,
     output: &str,
-) {
-    let objective_fn = match objective {
-        "binary:logistic" => parameters::learning::Objective::BinaryLogistic,
-        "multi:softmax" => parameters::learning::Objective::MultiSoftmax(nclasses),
-        "multi:softprob" => parameters::learning::Objective::MultiSoftprob(nclasses),
-        _ => {
-            eprintln!("invalid objective function");
-            std::process::exit(1);
+    labels: Option<&str>,
+) -> Result<History, XgbError> {
+    let mut cached = vec![training_set];
+    cached.extend(valid);
+
+    let mut bst = new_booster(&cached, objective, nclasses, params)?;
+
+    bst.set_attribute("objective", objective)?;
+    if let Some(labels) = labels {
+        bst.set_attribute("labels", labels)?;
+    }
+
+    let mut history = History::default();
+    let mut best_score = 0.0;
+
+    for round in 0..params.rounds as usize {
+        boost(&mut bst, training_set, round, objective, params)?;
+
+        let (metric, score) = bst.evaluate(training_set)?;
+        let mut line = format!("[{}]\ttrain-{}:{}", round, metric, score);
+        history.train.push(score);
+        history.metric = metric;
+
+        let valid = match valid {
+            Some(valid) => valid,
+            None => {
+                eprintln!("{}", line);
+                continue;
+            }
+        };
+
+        let (metric, score) = bst.evaluate(valid)?;
+        line.push_str(&format!("\tvalid-{}:{}", metric, score));
+        history.valid.push(score);
+        eprintln!("{}", line);
+
+        let better = match history.best_iteration {
+            None => true,
+            Some(_) if higher_is_better(&metric) => score > best_score,
+            Some(_) => score < best_score,
+        };
+
+        if better {
+            history.best_iteration = Some(round);
+            best_score = score;
+
+            if params.early_stopping_rounds.is_some() {
+                save(&mut bst, output, &history, best_score)?;
+            }
+        }
+
+        if let (Some(best), Some(patience)) = (history.best_iteration, params.early_stopping_rounds)
+        {
+            if round - best >= patience as usize {
+                eprintln!("stopping, the best iteration is [{}]", best);
+                return Ok(history);
+            }
         }
+    }
+
+    if params.early_stopping_rounds.is_none() || history.best_iteration.is_none() {
+        save(&mut bst, output, &history, best_score)?;
+    }
+
+    Ok(history)
+}
+
+/// save writes a model with the best iteration of its history
+fn save(
+    bst: &mut Booster,
+    output: &str,
+    history: &History,
+    best_score: f32,
+) -> Result<(), XgbError> {
+    if let Some(best) = history.best_iteration {
+        bst.set_attribute("best_iteration", &best.to_string())?;
+        bst.set_attribute("best_score", &best_score.to_string())?;
+    }
+
+    bst.save(output)
+}
+
+/// CrossValidation holds the metric of each round of cv, per fold, and the predictions of
+/// each fold's booster on its held out rows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrossValidation {
+    pub metric: String,
+    /// train[round][fold]
+    pub train: Vec<Vec<f32>>,
+    /// valid[round][fold]
+    pub valid: Vec<Vec<f32>>,
+    /// the round with the best mean validation score
+    pub best_iteration: usize,
+    /// the predictions of each fold, at the best iteration with early stopping and at the last
+    /// round otherwise
+    pub predictions: Vec<Vec<f32>>,
+}
+
+/// cv trains a booster on each (training, held out) pair of folds in lock step, so early
+/// stopping stops every fold at once, on the mean validation score
+pub fn cv(
+    folds: &[(DMatrix, DMatrix)],
+    objective: &str,
+    nclasses: u32,
+    params: &Params,
+) -> Result<CrossValidation, XgbError> {
+    let mut boosters = folds
+        .iter()
+        .map(|(train, valid)| new_booster(&[train, valid], objective, nclasses, params))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let predict = |boosters: &[Booster]| -> Result<Vec<Vec<f32>>, XgbError> {
+        boosters
+            .iter()
+            .zip(folds)
+            .map(|(bst, (_, valid))| bst.predict(valid))
+            .collect()
     };
 
-    let learning_params = parameters::learning::LearningTaskParametersBuilder::default()
-        .objective(objective_fn)
-        .build()
-        .unwrap();
-
-    let tree_params = parameters::tree::TreeBoosterParametersBuilder::default()
-        .max_depth(depth)
-        .eta(eta)
-        .build()
-        .unwrap();
-
-    let booster_params = parameters::BoosterParametersBuilder::default()
-        .booster_type(parameters::BoosterType::Tree(tree_params))
-        .learning_params(learning_params)
-        .verbose(false)
-        .build()
-        .unwrap();
-
-    let training_params = parameters::TrainingParametersBuilder::default()
-        .dtrain(training_set)
-        .booster_params(booster_params)
-        .boost_rounds(rounds)
-        .build()
-        .unwrap();
-
-    let bst = Booster::train(&training_params).unwrap();
-    for (k, v) in bst.evaluate(training_set).unwrap() {
-        eprintln!("{} = {}", k, v);
+    let mut result = CrossValidation::default();
+    let mut best_score = 0.0;
+
+    for round in 0..params.rounds as usize {
+        let mut train_scores = vec![];
+        let mut valid_scores = vec![];
+
+        for (bst, (train, valid)) in boosters.iter_mut().zip(folds) {
+            boost(bst, train, round, objective, params)?;
+
+            let (metric, score) = bst.evaluate(train)?;
+            train_scores.push(score);
+            valid_scores.push(bst.evaluate(valid)?.1);
+            result.metric = metric;
+        }
+
+        let score = valid_scores.iter().sum::<f32>() / valid_scores.len() as f32;
+        result.train.push(train_scores);
+        result.valid.push(valid_scores);
+
+        let better = round == 0
+            || if higher_is_better(&result.metric) {
+                score > best_score
+            } else {
+                score < best_score
+            };
+
+        if better {
+            result.best_iteration = round;
+            best_score = score;
+
+            if params.early_stopping_rounds.is_some() {
+                result.predictions = predict(&boosters)?;
+            }
+        }
+
+        if let Some(patience) = params.early_stopping_rounds {
+            if round - result.best_iteration >= patience as usize {
+                return Ok(result);
+            }
+        }
+    }
+
+    if params.early_stopping_rounds.is_none() {
+        result.predictions = predict(&boosters)?;
     }
 
-    let _ = bst.save(output).unwrap();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_pseudo_huber() {
+        let (grad, hess) = pseudo_huber(&[1.0, 0.0, 10.0], &[1.0, 1.0, 0.0], 1.0);
+
+        // no error, then about the squared error near 0 and the absolute error far from it
+        assert_eq!((grad[0], hess[0]), (0.0, 1.0));
+        assert!((grad[1] + 0.5f32.sqrt()).abs() < 1e-6);
+        assert!((hess[1] - 0.5f32.powf(1.5)).abs() < 1e-6);
+        assert!(grad[2] > 0.99 && grad[2] < 1.0);
+        assert!(hess[2] < 1e-3);
+
+        let (grad, _) = pseudo_huber(&[10.0], &[0.0], 100.0);
+        assert!((grad[0] - 10.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_objective() {
+        let params = Params::default();
+
+        let pairs = booster_params("reg:pseudohubererror", 1, &params).unwrap();
+        assert!(pairs.contains(&("objective", "reg:linear".to_string())));
+
+        let pairs = booster_params("rank:ndcg", 1, &params).unwrap();
+        assert!(pairs.contains(&("objective", "rank:ndcg".to_string())));
+
+        let pairs = booster_params("multi:softprob", 3, &params).unwrap();
+        assert!(pairs.contains(&("num_class", "3".to_string())));
+
+        let early = Params {
+            early_stopping_rounds: Some(5),
+            ..Params::default()
+        };
+        assert!(matches!(
+            booster_params("reg:pseudohubererror", 1, &early),
+            Err(XgbError::Param(_))
+        ));
+        assert!(booster_params("reg:squarederror", 1, &early).is_ok());
+
+        let early = Params {
+            eval_metric: Some(Metric::Mae),
+            ..early
+        };
+        assert!(booster_params("reg:pseudohubererror", 1, &early).is_ok());
+
+        assert_eq!(
+            booster_params("reg:huber", 1, &params),
+            Err(XgbError::Objective("reg:huber".to_string()))
+        );
+    }
 }
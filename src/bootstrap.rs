@@ -0,0 +1,98 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Bootstrap draws resamples of row indices with replacement. With a seed the resamples are
+/// the same on every run, otherwise the generator is seeded from the OS.
+pub struct Bootstrap {
+    rng: StdRng,
+    n: usize,
+}
+
+impl Bootstrap {
+    pub fn new(n: usize, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Bootstrap { rng, n }
+    }
+
+    /// sample fills indices with n row indices drawn with replacement
+    pub fn sample(&mut self, indices: &mut Vec<usize>) {
+        indices.clear();
+
+        for _ in 0..self.n {
+            indices.push(self.rng.gen_range(0..self.n));
+        }
+    }
+}
+
+/// interval returns the percentile confidence interval of the estimates of one metric. NaN
+/// estimates, e.g. the AUC of a resample with a single class, are left out, and the interval
+/// is NaN when every estimate is.
+pub fn interval(estimates: &[f64], ci: f64) -> (f64, f64) {
+    let mut sorted: Vec<f64> = estimates.iter().copied().filter(|x| !x.is_nan()).collect();
+
+    if sorted.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let alpha = (1.0 - ci) / 2.0;
+    let typ = series::QuantileType::default();
+
+    (
+        series::quantile(&sorted, alpha, typ),
+        series::quantile(&sorted, 1.0 - alpha, typ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        let (mut a, mut b) = (vec![], vec![]);
+        let mut first = Bootstrap::new(50, Some(3));
+        let mut second = Bootstrap::new(50, Some(3));
+
+        for _ in 0..3 {
+            first.sample(&mut a);
+            second.sample(&mut b);
+            assert_eq!(a, b);
+            assert_eq!(a.len(), 50);
+            assert!(a.iter().all(|i| *i < 50));
+        }
+
+        // resampled with replacement, so some rows repeat
+        a.sort_unstable();
+        a.dedup();
+        assert!(a.len() < 50);
+
+        Bootstrap::new(50, Some(4)).sample(&mut b);
+        Bootstrap::new(50, Some(3)).sample(&mut a);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_interval() {
+        // 0 to 100 in reverse, with NaN estimates mixed in
+        let mut estimates: Vec<f64> = (0..=100).rev().map(|x| x as f64).collect();
+        estimates.insert(10, f64::NAN);
+        estimates.push(f64::NAN);
+
+        let (lower, upper) = interval(&estimates, 0.9);
+        assert!((lower - 5.0).abs() < 1e-9, "{}", lower);
+        assert!((upper - 95.0).abs() < 1e-9, "{}", upper);
+
+        let (lower, upper) = interval(&estimates, 0.5);
+        assert!((lower - 25.0).abs() < 1e-9, "{}", lower);
+        assert!((upper - 75.0).abs() < 1e-9, "{}", upper);
+
+        let (lower, upper) = interval(&[f64::NAN, f64::NAN], 0.95);
+        assert!(lower.is_nan() && upper.is_nan());
+    }
+}
@@ -0,0 +1,310 @@
+/// width of the left hand margin used for the y-axis labels
+const MARGIN: usize = 10;
+
+/// partial block characters, in eighths, used to draw the tail of a histogram bar
+const BLOCKS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// Canvas is a grid of braille characters. Each character is a 2x4 grid of dots, so
+/// a canvas of width w and height h has (2 * w) x (4 * h) addressable pixels.
+struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    fn pixel_width(&self) -> usize {
+        self.width * 2
+    }
+
+    fn pixel_height(&self) -> usize {
+        self.height * 4
+    }
+
+    /// turn on the pixel at (x, y), with (0, 0) the top left of the canvas
+    fn set(&mut self, x: usize, y: usize) {
+        if x >= self.pixel_width() || y >= self.pixel_height() {
+            return;
+        }
+
+        // braille dot numbering, see https://en.wikipedia.org/wiki/Braille_Patterns
+        let bit = match (x % 2, y % 4) {
+            (0, 0) => 0x01,
+            (0, 1) => 0x02,
+            (0, 2) => 0x04,
+            (1, 0) => 0x08,
+            (1, 1) => 0x10,
+            (1, 2) => 0x20,
+            (0, 3) => 0x40,
+            _ => 0x80,
+        };
+
+        self.cells[(y / 4) * self.width + (x / 2)] |= bit;
+    }
+
+    /// draw a line between two pixels using Bresenham's algorithm
+    fn line(&mut self, from: (usize, usize), to: (usize, usize)) {
+        let (mut x0, mut y0) = (from.0 as i64, from.1 as i64);
+        let (x1, y1) = (to.0 as i64, to.1 as i64);
+
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            self.set(x0 as usize, y0 as usize);
+
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+
+    fn rows(&self) -> Vec<String> {
+        self.cells
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|b| char::from_u32(0x2800 + *b as u32).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Bounds of the plotted data, used to scale points onto a canvas
+struct Bounds {
+    xmin: f64,
+    xmax: f64,
+    ymin: f64,
+    ymax: f64,
+}
+
+impl Bounds {
+    fn new(points: &[(f64, f64)]) -> Self {
+        let mut bounds = Bounds {
+            xmin: points[0].0,
+            xmax: points[0].0,
+            ymin: points[0].1,
+            ymax: points[0].1,
+        };
+
+        for (x, y) in points {
+            bounds.xmin = bounds.xmin.min(*x);
+            bounds.xmax = bounds.xmax.max(*x);
+            bounds.ymin = bounds.ymin.min(*y);
+            bounds.ymax = bounds.ymax.max(*y);
+        }
+
+        bounds
+    }
+
+    /// maps a point into the pixel space of the canvas, y grows downwards
+    fn scale(&self, canvas: &Canvas, point: (f64, f64)) -> (usize, usize) {
+        let w = (canvas.pixel_width() - 1) as f64;
+        let h = (canvas.pixel_height() - 1) as f64;
+
+        let x = if self.xmax > self.xmin {
+            (point.0 - self.xmin) / (self.xmax - self.xmin) * w
+        } else {
+            w / 2.0
+        };
+
+        let y = if self.ymax > self.ymin {
+            (point.1 - self.ymin) / (self.ymax - self.ymin) * h
+        } else {
+            h / 2.0
+        };
+
+        (x.round() as usize, (h - y.round()) as usize)
+    }
+}
+
+/// adds the y-axis labels, the x-axis and its labels around the rendered canvas
+fn frame(canvas: &Canvas, bounds: &Bounds) -> String {
+    let mut out = String::new();
+    let rows = canvas.rows();
+    let last = rows.len() - 1;
+
+    for (index, row) in rows.iter().enumerate() {
+        let (label, tick) = if index == 0 {
+            (format!("{:.4}", bounds.ymax), '┤')
+        } else if index == last {
+            (format!("{:.4}", bounds.ymin), '┤')
+        } else {
+            (String::new(), '│')
+        };
+
+        out.push_str(&format!("{:>w$} {}{}\n", label, tick, row, w = MARGIN));
+    }
+
+    out.push_str(&format!(
+        "{:>w$} └{}\n",
+        "",
+        "─".repeat(canvas.width),
+        w = MARGIN
+    ));
+
+    let xmin = format!("{:.4}", bounds.xmin);
+    out.push_str(&format!(
+        "{:>w$}  {}{:>r$}\n",
+        "",
+        xmin,
+        format!("{:.4}", bounds.xmax),
+        w = MARGIN,
+        r = canvas.width.saturating_sub(xmin.len())
+    ));
+
+    out
+}
+
+/// histogram draws one horizontal bar per bin, scaled so the largest bin fills the width
+pub fn histogram(input: &[f64], bins: usize, width: usize) -> String {
+    let hist = series::histogram(input, bins);
+    let max = hist.iter().map(|(_, _, c)| *c).max().unwrap_or(0);
+
+    let mut out = String::new();
+
+    for (lower, upper, count) in hist {
+        // length of the bar in eighths of a character
+        let eighths = (count * width * 8).checked_div(max).unwrap_or(0);
+
+        let mut bar = "█".repeat(eighths / 8);
+        if eighths % 8 > 0 {
+            bar.push(BLOCKS[eighths % 8 - 1]);
+        }
+
+        out.push_str(&format!(
+            "{:>w$.4} - {:<w$.4} │{} {}\n",
+            lower,
+            upper,
+            bar,
+            count,
+            w = MARGIN
+        ));
+    }
+
+    out
+}
+
+/// line plots the points in the order given, connecting each point to the next
+pub fn line(points: &[(f64, f64)], width: usize, height: usize) -> String {
+    let mut canvas = Canvas::new(width, height);
+    let bounds = Bounds::new(points);
+
+    let pixels: Vec<(usize, usize)> = points.iter().map(|p| bounds.scale(&canvas, *p)).collect();
+
+    if pixels.len() == 1 {
+        canvas.set(pixels[0].0, pixels[0].1);
+    }
+
+    for pair in pixels.windows(2) {
+        canvas.line(pair[0], pair[1]);
+    }
+
+    frame(&canvas, &bounds)
+}
+
+/// scatter plots each point as a single braille dot
+pub fn scatter(points: &[(f64, f64)], width: usize, height: usize) -> String {
+    let mut canvas = Canvas::new(width, height);
+    let bounds = Bounds::new(points);
+
+    for point in points {
+        let (x, y) = bounds.scale(&canvas, *point);
+        canvas.set(x, y);
+    }
+
+    frame(&canvas, &bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// bars returns the bar of each line of a histogram
+    fn bars(out: &str) -> Vec<String> {
+        out.lines()
+            .map(|line| {
+                let bar = line.split('│').nth(1).unwrap();
+                bar.rsplit_once(' ').unwrap().0.to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_histogram() {
+        // the largest bin fills the width, the other is 2/3 of it, 21 eighths
+        let out = histogram(&[0.0, 0.0, 0.0, 1.0, 1.0], 2, 4);
+        assert_eq!(bars(&out), ["████", "██▋"]);
+        assert!(out.lines().next().unwrap().ends_with(" 3"));
+
+        // an empty bin has no bar
+        let out = histogram(&[0.0, 2.0], 3, 4);
+        assert_eq!(bars(&out), ["████", "", "████"]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let bounds = Bounds::new(&[(1.0, -2.0), (3.0, 4.0), (2.0, 0.0)]);
+        assert_eq!(
+            (bounds.xmin, bounds.xmax, bounds.ymin, bounds.ymax),
+            (1.0, 3.0, -2.0, 4.0)
+        );
+
+        // 4x8 pixels, y grows downwards
+        let canvas = Canvas::new(2, 2);
+        assert_eq!(bounds.scale(&canvas, (1.0, -2.0)), (0, 7));
+        assert_eq!(bounds.scale(&canvas, (3.0, 4.0)), (3, 0));
+        assert_eq!(bounds.scale(&canvas, (2.0, 1.0)), (2, 3));
+
+        // a single point is drawn in the middle
+        let bounds = Bounds::new(&[(5.0, 5.0)]);
+        assert_eq!(bounds.scale(&canvas, (5.0, 5.0)), (2, 3));
+    }
+
+    #[test]
+    fn test_canvas() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set(0, 0);
+        canvas.set(3, 3);
+        // off the canvas
+        canvas.set(4, 0);
+        canvas.set(0, 4);
+        assert_eq!(canvas.rows(), ["⠁⢀"]);
+
+        // the dots of a cell, left column then right, with the bottom row last
+        let mut canvas = Canvas::new(1, 1);
+        for (x, y) in [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3)] {
+            canvas.set(x, y);
+        }
+        assert_eq!(canvas.rows(), ["⡿"]);
+        canvas.set(1, 3);
+        assert_eq!(canvas.rows(), ["⣿"]);
+
+        let mut canvas = Canvas::new(2, 1);
+        canvas.line((0, 3), (3, 0));
+        assert_eq!(canvas.rows(), ["⡠⠊"]);
+    }
+}
@@ -1,9 +1,17 @@
 use murmur3::murmur3_32;
 use std::io::prelude::*;
 use std::io::Cursor;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
+mod bootstrap;
+mod graph;
+mod report;
+mod tune;
+
+use report::{Format, Section};
+use serde_json::Value;
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "st",
@@ -11,10 +19,216 @@ use structopt::StructOpt;
     version = "0.1"
 )]
 struct Opt {
+    #[structopt(
+        long,
+        global = true,
+        help = "output format: table, csv, tsv, json, ndjson",
+        default_value = "table"
+    )]
+    format: Format,
+
     #[structopt(subcommand)]
     cmd: Command,
 }
 
+// InputOptions are how a command reads the rows of its input. Not a doc comment, as structopt
+// would make it the about of every command that flattens it.
+#[derive(StructOpt, Debug)]
+struct InputOptions {
+    #[structopt(short = "h", long = "with-header", help = "with header")]
+    with_header: bool,
+
+    #[structopt(
+        short = "F",
+        long,
+        help = "field delimiter: a single character, csv, tsv, or ws for whitespace",
+        default_value = ","
+    )]
+    delimiter: series::Delimiter,
+
+    #[structopt(
+        long,
+        help = "missing values: error, skip-row, skip-cell, impute-mean, impute-median, impute-const[=value], nan. Defaults to error, or nan for xgb"
+    )]
+    missing: Option<series::Missing>,
+
+    #[structopt(parse(from_os_str))]
+    input: Option<PathBuf>,
+}
+
+impl InputOptions {
+    /// parts splits the options up, with the default --missing policy of the command
+    fn parts(
+        self,
+        missing: series::Missing,
+    ) -> (bool, series::Delimiter, series::Missing, Option<PathBuf>) {
+        (
+            self.with_header,
+            self.delimiter,
+            self.missing.unwrap_or(missing),
+            self.input,
+        )
+    }
+
+    /// given returns the options set on the command line, for the commands that don't take them
+    fn given(&self) -> Vec<&'static str> {
+        let mut given = vec![];
+        if self.with_header {
+            given.push("-h");
+        }
+        if self.delimiter != series::Delimiter::default() {
+            given.push("-F");
+        }
+        if self.missing.is_some() {
+            given.push("--missing");
+        }
+        if self.input.is_some() {
+            given.push("an input file");
+        }
+
+        given
+    }
+}
+
+// ParamOptions are the parameters of xgb train, cv and tune. Not a doc comment, as structopt
+// would make it the about of every command that flattens it.
+#[derive(StructOpt, Debug)]
+struct ParamOptions {
+    #[structopt(short = "d", long = "depth", help = "max depth, default 6")]
+    max_depth: Option<u32>,
+
+    #[structopt(short, long, help = "eta, the learning rate, default 0.3")]
+    eta: Option<f32>,
+
+    #[structopt(long, help = "fraction of rows sampled for each tree, default 1")]
+    subsample: Option<f32>,
+
+    #[structopt(long, help = "fraction of columns sampled for each tree, default 1")]
+    colsample_bytree: Option<f32>,
+
+    #[structopt(long, help = "fraction of columns sampled for each level, default 1")]
+    colsample_bylevel: Option<f32>,
+
+    #[structopt(long, help = "minimum hessian sum of a child, default 1")]
+    min_child_weight: Option<f32>,
+
+    #[structopt(long, help = "minimum loss reduction to split, default 0")]
+    gamma: Option<f32>,
+
+    #[structopt(long, help = "L2 regularization of the weights, default 1")]
+    lambda: Option<f32>,
+
+    #[structopt(long, help = "L1 regularization of the weights, default 0")]
+    alpha: Option<f32>,
+
+    #[structopt(long, help = "maximum step of each leaf weight, 0 for none, default 0")]
+    max_delta_step: Option<f32>,
+
+    #[structopt(long, help = "weight of the positive class, default 1")]
+    scale_pos_weight: Option<f32>,
+
+    #[structopt(long, help = "tree method: auto, exact, approx or hist, default auto")]
+    tree_method: Option<xgb::TreeMethod>,
+
+    #[structopt(
+        long,
+        help = "most bins of a feature with the hist tree method, default 256"
+    )]
+    max_bin: Option<u32>,
+
+    #[structopt(long, help = "initial prediction of every row, default 0.5")]
+    base_score: Option<f32>,
+
+    #[structopt(long, help = "random seed of the sampling, default 0")]
+    seed: Option<u64>,
+
+    #[structopt(long, help = "variance power of reg:tweedie, from 1 to 2, default 1.5")]
+    tweedie_variance_power: Option<f32>,
+
+    #[structopt(long, help = "slope of reg:pseudohubererror, default 1")]
+    huber_slope: Option<f32>,
+
+    #[structopt(
+        long = "params",
+        parse(from_os_str),
+        help = "json file of parameters by their xgboost names, flags take precedence"
+    )]
+    params_file: Option<PathBuf>,
+
+    #[structopt(short, long, help = "how many boosted rounds, default is 10")]
+    rounds: Option<u32>,
+
+    #[structopt(
+        long = "early-stopping",
+        help = "stop when the validation metric hasn't improved for this many rounds"
+    )]
+    early_stopping_rounds: Option<u32>,
+
+    #[structopt(
+        long,
+        help = "metric: rmse, mae, logloss, error, merror, mlogloss, auc, ndcg or map, by default the objective's"
+    )]
+    eval_metric: Option<xgb::Metric>,
+}
+
+impl ParamOptions {
+    /// params resolves the parameters, flags override the params file, which overrides the
+    /// defaults
+    fn params(self) -> xgb::Params {
+        let mut params = match &self.params_file {
+            Some(path) => read_params(path),
+            None => xgb::Params::default(),
+        };
+
+        macro_rules! set {
+            ($($flag:ident),*) => {
+                $(if let Some(value) = self.$flag {
+                    params.$flag = value;
+                })*
+            };
+        }
+
+        set!(
+            max_depth,
+            eta,
+            subsample,
+            colsample_bytree,
+            colsample_bylevel,
+            min_child_weight,
+            gamma,
+            lambda,
+            alpha,
+            max_delta_step,
+            scale_pos_weight,
+            tree_method,
+            max_bin,
+            base_score,
+            seed,
+            rounds
+        );
+
+        if self.tweedie_variance_power.is_some() {
+            params.tweedie_variance_power = self.tweedie_variance_power;
+        }
+
+        if self.huber_slope.is_some() {
+            params.huber_slope = self.huber_slope;
+        }
+
+        if self.eval_metric.is_some() {
+            params.eval_metric = self.eval_metric;
+        }
+
+        if self.early_stopping_rounds.is_some() {
+            params.early_stopping_rounds = self.early_stopping_rounds;
+        }
+
+        params
+    }
+}
+
+// parsed once, so the size of the train options doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(StructOpt, Debug)]
 enum XgbOptions {
     #[structopt(about = "train a new binary or multiclass model")]
@@ -22,11 +236,8 @@ enum XgbOptions {
         #[structopt(short, long, help = "predictor column")]
         ycol: usize,
 
-        #[structopt(short, long, help = "max depth", default_value = "6")]
-        depth: u32,
-
-        #[structopt(short, long, help = "eta", default_value = "0.3")]
-        eta: f32,
+        #[structopt(flatten)]
+        params: ParamOptions,
 
         #[structopt(short = "m", long, help = "path to save model")]
         model_out: String,
@@ -34,10 +245,17 @@ enum XgbOptions {
         #[structopt(
             short,
             long,
-            help = "objective function: binary:logistic, multi:softmax, multi:softprob"
+            help = "objective function: binary:logistic, multi:softmax, multi:softprob, reg:squarederror, reg:logistic, reg:pseudohubererror, reg:gamma, reg:tweedie, count:poisson, survival:cox, rank:pairwise, rank:ndcg"
         )]
         objective: String,
 
+        #[structopt(
+            short,
+            long,
+            help = "group column of a rank objective, e.g. a query id, the rows of a group must be contiguous"
+        )]
+        group: Option<usize>,
+
         #[structopt(
             short,
             long,
@@ -46,19 +264,173 @@ enum XgbOptions {
         )]
         nclasses: u32,
 
+        #[structopt(long, parse(from_os_str), help = "validation set, read like the input")]
+        valid: Option<PathBuf>,
+
+        #[structopt(
+            long,
+            help = "hold out this fraction of the input as the validation set, by group with -g"
+        )]
+        valid_frac: Option<f64>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "write the metric of each round to a csv file"
+        )]
+        eval_log: Option<PathBuf>,
+
         #[structopt(
             short,
             long,
-            help = "how many boosted rounds, default is 10",
-            default_value = "10"
+            help = "columns to use as features: an index, a range (1-3), or a name with a header"
         )]
-        rounds: u32,
+        column: Option<series::Columns>,
 
-        #[structopt(short = "h", long = "with-header", help = "with header")]
-        with_header: bool,
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
 
-        #[structopt(parse(from_os_str))]
-        input: Option<PathBuf>,
+    #[structopt(about = "cross validate a model with k folds")]
+    Cv {
+        #[structopt(short, long, help = "predictor column")]
+        ycol: usize,
+
+        #[structopt(short, long = "folds", help = "number of folds", default_value = "5")]
+        k: usize,
+
+        #[structopt(long, help = "give every fold the same share of each class")]
+        stratify: bool,
+
+        #[structopt(flatten)]
+        params: ParamOptions,
+
+        #[structopt(
+            short,
+            long,
+            help = "objective function: binary:logistic, multi:softmax, multi:softprob, reg:squarederror, reg:logistic, reg:pseudohubererror, reg:gamma, reg:tweedie, count:poisson, survival:cox, rank:pairwise, rank:ndcg"
+        )]
+        objective: String,
+
+        #[structopt(
+            short,
+            long,
+            help = "group column of a rank objective, e.g. a query id, the rows of a group must be contiguous"
+        )]
+        group: Option<usize>,
+
+        #[structopt(
+            short,
+            long,
+            help = "number of classes to predict",
+            default_value = "1"
+        )]
+        nclasses: u32,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "write the out of fold predictions, each followed by the actual label, for st eval"
+        )]
+        oof: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            help = "columns to use as features: an index, a range (1-3), or a name with a header"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(about = "search for the best parameters of a model")]
+    Tune {
+        #[structopt(short, long, help = "predictor column")]
+        ycol: usize,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "search space, a json object of a list of choices or a {min, max, log} range per parameter"
+        )]
+        space: PathBuf,
+
+        #[structopt(long, help = "search: grid or random", default_value = "random")]
+        search: tune::Search,
+
+        #[structopt(long, help = "number of random trials, 20 by default")]
+        trials: Option<usize>,
+
+        #[structopt(
+            short,
+            long = "folds",
+            help = "number of folds, 5 by default",
+            conflicts_with = "valid-frac"
+        )]
+        k: Option<usize>,
+
+        #[structopt(
+            long,
+            help = "validate on this fraction of the input held out, by group with -g, instead of folds"
+        )]
+        valid_frac: Option<f64>,
+
+        #[structopt(long, help = "give every fold the same share of each class")]
+        stratify: bool,
+
+        #[structopt(
+            short,
+            long,
+            help = "number of trials run at once, the number of cores by default"
+        )]
+        jobs: Option<usize>,
+
+        #[structopt(flatten)]
+        params: ParamOptions,
+
+        #[structopt(short = "m", long, help = "path to save the best model")]
+        model_out: String,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "path to save the best parameters, the model path with .json appended by default"
+        )]
+        params_out: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            help = "objective function: binary:logistic, multi:softmax, multi:softprob, reg:squarederror, reg:logistic, reg:pseudohubererror, reg:gamma, reg:tweedie, count:poisson, survival:cox, rank:pairwise, rank:ndcg"
+        )]
+        objective: String,
+
+        #[structopt(
+            short,
+            long,
+            help = "group column of a rank objective, e.g. a query id, the rows of a group must be contiguous"
+        )]
+        group: Option<usize>,
+
+        #[structopt(
+            short,
+            long,
+            help = "number of classes to predict",
+            default_value = "1"
+        )]
+        nclasses: u32,
+
+        #[structopt(
+            short,
+            long,
+            help = "columns to use as features: an index, a range (1-3), or a name with a header"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(flatten)]
+        input: InputOptions,
     },
 
     #[structopt(about = "use an xgboost model against some input")]
@@ -69,11 +441,22 @@ enum XgbOptions {
         #[structopt(short, long, help = "path to model")]
         model_in: String,
 
-        #[structopt(short = "h", long = "with-header", help = "with header")]
-        with_header: bool,
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "calibrate the probabilities of a binary model, see calibrate fit"
+        )]
+        calibrator: Option<PathBuf>,
+
+        #[structopt(
+            short,
+            long,
+            help = "columns to use as features: an index, a range (1-3), or a name with a header"
+        )]
+        column: Option<series::Columns>,
 
-        #[structopt(parse(from_os_str))]
-        input: Option<PathBuf>,
+        #[structopt(flatten)]
+        input: InputOptions,
     },
 
     #[structopt(about = "dump model importance statistics, and text version of the model itself")]
@@ -132,126 +515,609 @@ enum ExtractOptions {
 }
 
 #[derive(StructOpt, Debug)]
-enum Command {
-    #[structopt(about = "summary statistics from a single vector")]
-    Summary {
-        #[structopt(short)]
-        transpose: bool,
+enum GraphOptions {
+    #[structopt(about = "histogram of a single vector")]
+    Hist {
+        #[structopt(short, long, help = "number of bins", default_value = "10")]
+        bins: usize,
 
-        #[structopt(short = "h", long = "with-header")]
-        with_header: bool,
+        #[structopt(short, long, help = "width of the graph", default_value = "60")]
+        width: usize,
 
-        #[structopt(parse(from_os_str))]
-        input: Option<PathBuf>,
+        #[structopt(flatten)]
+        input: InputOptions,
     },
 
-    #[structopt(about = "k-quintile from a single vector (default k = 5)")]
-    Quintiles {
-        #[structopt(
-            short = "k",
-            help = "k-quintile, for some input k",
-            default_value = "5"
-        )]
-        quintiles: u32,
+    #[structopt(
+        about = "line plot of a single vector against its index, or of two columns as x,y"
+    )]
+    Line {
+        #[structopt(short, long, help = "width of the graph", default_value = "60")]
+        width: usize,
 
-        #[structopt(short = "h", long = "with-header")]
-        with_header: bool,
+        #[structopt(short = "H", long, help = "height of the graph", default_value = "15")]
+        height: usize,
 
-        #[structopt(parse(from_os_str))]
-        input: Option<PathBuf>,
+        #[structopt(flatten)]
+        input: InputOptions,
     },
 
-    #[structopt(about = "train, predict, and understand xgboost models")]
-    Xgb(XgbOptions),
-
-    #[structopt(about = "Computes the Pearson correlation coefficient")]
-    CorMatrix {
-        #[structopt(short, long, help = "predictor column", default_value = "1000000")]
-        ycol: usize,
+    #[structopt(
+        about = "scatter plot of a single vector against its index, or of two columns as x,y"
+    )]
+    Scatter {
+        #[structopt(short, long, help = "width of the graph", default_value = "60")]
+        width: usize,
 
-        #[structopt(short = "h", long = "with-header", help = "with header")]
-        with_header: bool,
+        #[structopt(short = "H", long, help = "height of the graph", default_value = "15")]
+        height: usize,
 
-        #[structopt(parse(from_os_str))]
-        input: Option<PathBuf>,
+        #[structopt(flatten)]
+        input: InputOptions,
     },
+}
 
+#[derive(StructOpt, Debug)]
+enum CalibrateOptions {
     #[structopt(
-        about = "evaluation metrics to score an output, confusion matrix and other helpful
-        probablities. Note: all classes need to be 0..N"
+        about = "fit a calibrator to score,actual tuples of a binary prediction and save it to a file"
     )]
-    Eval {
+    Fit {
         #[structopt(
             short,
             long,
-            help = "if value is (0,1) set a threshold at which the value will be converted to 1"
+            help = "platt, a logistic curve, or isotonic, a non decreasing step function",
+            default_value = "platt"
         )]
-        threshold: Option<f32>,
+        method: series::Method,
 
-        #[structopt(short, long, parse(from_occurrences), help = "show verbose output")]
-        verbose: u32,
+        #[structopt(short, long, parse(from_os_str), help = "path to save the calibrator")]
+        output: PathBuf,
 
         #[structopt(
             short,
             long,
-            help = "Use bayes theorem to estimate the effective probability using a estimate of the
-            true rate of occurance for each class. This value expects a string of floats, one for
-            each class in the dataset. E.g. -b '0.1, 0.2, 0.3'"
+            help = "the score and actual columns, in that order, e.g. -c 3,0"
         )]
-        bayes: Option<String>,
+        column: Option<series::Columns>,
 
-        #[structopt(parse(from_os_str))]
-        input: Option<PathBuf>,
+        #[structopt(flatten)]
+        input: InputOptions,
     },
 
-    #[structopt(about = "data transformations and feature generation tools")]
-    Extract(ExtractOptions),
-}
-
-/// get_input will check if the input parameter is_some, and if so read input from a file, else,
-/// read input from stdin. A new String is returned with the contents.
-fn get_input(input: Option<PathBuf>) -> String {
-    if let Some(path) = input {
-        match std::fs::read_to_string(path) {
-            Ok(s) => s,
-            Err(_) => {
-                eprintln!("failed to read input file");
-                std::process::exit(1);
-            }
-        }
-    } else {
-        let mut input = String::new();
-        let stdin = std::io::stdin();
+    #[structopt(
+        about = "replace a score column with calibrated probabilities, the other columns are kept"
+    )]
+    Apply {
+        #[structopt(short, long, parse(from_os_str), help = "path to the calibrator")]
+        model_in: PathBuf,
 
-        for line in stdin.lock().lines() {
-            let buf = line.expect("failed to read line");
-            input.push_str(&buf);
-            input.push('\n');
-        }
+        #[structopt(short, long, help = "the score column", default_value = "0")]
+        column: series::Columns,
 
-        input
-    }
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
 }
 
-fn get_input_bytes(input: Option<PathBuf>) -> Vec<u8> {
+#[derive(StructOpt, Debug)]
+enum QuantilesOptions {
+    #[structopt(
+        about = "merge t-digest sketches, e.g. of shards of an input, and print quantiles"
+    )]
+    Merge {
+        #[structopt(
+            short = "k",
+            help = "k-quantiles, for some input k",
+            default_value = "5"
+        )]
+        quintiles: usize,
+
+        #[structopt(
+            short,
+            long,
+            help = "probabilities to use instead of k-quantiles, e.g. 0.01,0.5,0.99"
+        )]
+        probabilities: Option<String>,
+
+        #[structopt(
+            short,
+            long,
+            parse(from_os_str),
+            help = "write the merged sketch to a file"
+        )]
+        output: Option<PathBuf>,
+
+        #[structopt(parse(from_os_str), required = true)]
+        inputs: Vec<PathBuf>,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+enum EvalOptions {
+    #[structopt(about = "error metrics of a regression model from predicted,actual tuples")]
+    Regression {
+        #[structopt(
+            short,
+            long,
+            help = "the predicted and actual columns, in that order, e.g. -c 3,0"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(
+            short,
+            long,
+            help = "probabilities of the residual quantile table",
+            default_value = "0,0.05,0.25,0.5,0.75,0.95,1"
+        )]
+        probabilities: String,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(
+        about = "multiclass metrics of softprob output, a probability for each class followed by
+        the actual class on each row"
+    )]
+    Softprob {
+        #[structopt(
+            short,
+            long,
+            help = "the probability columns and then the actual column, e.g. -c 0-2,3"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(
+            short = "k",
+            long,
+            help = "report the top-k accuracy for k from 1 up to this",
+            default_value = "3"
+        )]
+        top_k: usize,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(
+        about = "reliability diagram of predicted probabilities, with the Brier score and the
+        expected and maximum calibration error. Rows are a probability and the actual class of a
+        binary prediction, or softprob rows where the most probable class of each row is binned"
+    )]
+    Calibration {
+        #[structopt(
+            short,
+            long,
+            help = "the probability columns and then the actual column, e.g. -c 3,0"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(short, long, help = "number of bins", default_value = "10")]
+        bins: usize,
+
+        #[structopt(
+            long,
+            help = "bin edges: uniform, bins of the same width, or quantile, bins of the same size",
+            default_value = "uniform"
+        )]
+        binning: series::Binning,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(
+        about = "compare two models on the same rows of pred_a,pred_b,actual with McNemar's test,
+        DeLong's test of the AUCs when the predictions are scores, and a paired bootstrap of the
+        differences"
+    )]
+    Compare {
+        #[structopt(
+            short,
+            long,
+            help = "the columns of model a, model b and the actual class, in that order, e.g. -c 1,2,0"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(
+            short,
+            long,
+            help = "a score at or above this is the second class for McNemar's test and the accuracy",
+            default_value = "0.5"
+        )]
+        threshold: f64,
+
+        #[structopt(
+            long,
+            help = "resample the rows this many times for the paired bootstrap, 0 to skip it",
+            default_value = "1000"
+        )]
+        bootstrap: usize,
+
+        #[structopt(
+            long,
+            help = "the confidence level of the bootstrap intervals",
+            default_value = "0.95"
+        )]
+        ci: f64,
+
+        #[structopt(long, help = "seed the bootstrap resamples so runs are reproducible")]
+        seed: Option<u64>,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(
+        about = "exact ROC and precision-recall curves of a binary prediction, with the ROC AUC and
+        average precision"
+    )]
+    Roc {
+        #[structopt(
+            short,
+            long,
+            help = "the score and actual columns, in that order, e.g. -c 3,0"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "write a point of both curves for every distinct score to a file, as csv unless
+            --format is tsv, json or ndjson"
+        )]
+        curve: Option<PathBuf>,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    #[structopt(about = "summary statistics of a single vector, or of each column of a matrix")]
+    Summary {
+        #[structopt(short)]
+        transpose: bool,
+
+        #[structopt(
+            short,
+            long,
+            help = "columns to use: an index, a range (1-3), or a name with a header"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(
+            short,
+            long,
+            help = "percentiles to include, e.g. 5,50,95",
+            default_value = "25,50,75"
+        )]
+        percentiles: String,
+
+        #[structopt(
+            long,
+            help = "estimate the quantiles with a t-digest, reading the input once in bounded memory"
+        )]
+        approx: bool,
+
+        #[structopt(
+            long,
+            help = "the most values kept for exact quantiles, beyond which they are estimated",
+            default_value = "10000000"
+        )]
+        exact_limit: usize,
+
+        #[structopt(
+            long,
+            help = "t-digest compression for --approx, larger is more accurate",
+            default_value = "100"
+        )]
+        compression: f64,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(about = "k-quantiles, or quantiles at given probabilities, from a single vector")]
+    Quintiles {
+        #[structopt(
+            short = "k",
+            help = "k-quantiles, for some input k",
+            default_value = "5"
+        )]
+        quintiles: usize,
+
+        #[structopt(
+            short,
+            long,
+            help = "probabilities to use instead of k-quantiles, e.g. 0.01,0.5,0.99"
+        )]
+        probabilities: Option<String>,
+
+        #[structopt(
+            short = "t",
+            long = "type",
+            help = "Hyndman-Fan quantile type, 1 to 9 as numbered in R",
+            default_value = "7"
+        )]
+        typ: series::QuantileType,
+
+        #[structopt(
+            long,
+            help = "estimate the quantiles with a t-digest, reading the input once in bounded memory"
+        )]
+        approx: bool,
+
+        #[structopt(
+            long,
+            help = "t-digest compression for --approx, larger is more accurate",
+            default_value = "100"
+        )]
+        compression: f64,
+
+        #[structopt(
+            long,
+            parse(from_os_str),
+            requires = "approx",
+            help = "write the t-digest to a file, for st quantiles merge"
+        )]
+        sketch: Option<PathBuf>,
+
+        #[structopt(short, long, help = "column to use: an index, or a name with a header")]
+        column: Option<series::Columns>,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(about = "combine the t-digest sketches of quintiles --approx")]
+    Quantiles(QuantilesOptions),
+
+    #[structopt(about = "fit and apply probability calibration, Platt scaling or isotonic")]
+    Calibrate(CalibrateOptions),
+
+    #[structopt(about = "train, predict, and understand xgboost models")]
+    Xgb(XgbOptions),
+
+    #[structopt(about = "Computes the Pearson correlation coefficient")]
+    CorMatrix {
+        #[structopt(short, long, help = "predictor column", default_value = "1000000")]
+        ycol: usize,
+
+        #[structopt(
+            short,
+            long,
+            help = "columns to correlate: an index, a range (1-3), or a name with a header"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+    },
+
+    #[structopt(
+        about = "evaluation metrics to score an output, confusion matrix and other helpful
+        probablities. Classes may be any labels, numbers or strings"
+    )]
+    Eval {
+        #[structopt(
+            short,
+            long,
+            help = "if value is (0,1) set a threshold at which the value will be converted to 1"
+        )]
+        threshold: Option<f32>,
+
+        #[structopt(short, long, parse(from_occurrences), help = "show verbose output")]
+        verbose: u32,
+
+        #[structopt(
+            short,
+            long,
+            help = "Use bayes theorem to estimate the effective probability using a estimate of the
+            true rate of occurance for each class. This value expects a string of floats, one for
+            each class in the dataset. E.g. -b '0.1, 0.2, 0.3'"
+        )]
+        bayes: Option<String>,
+
+        #[structopt(
+            long,
+            help = "resample the rows this many times for a confidence interval of every metric,
+            this holds the input in memory",
+            default_value = "0"
+        )]
+        bootstrap: usize,
+
+        #[structopt(
+            long,
+            help = "the confidence level of the --bootstrap intervals",
+            default_value = "0.95"
+        )]
+        ci: f64,
+
+        #[structopt(long, help = "seed the --bootstrap resamples so runs are reproducible")]
+        seed: Option<u64>,
+
+        #[structopt(
+            long,
+            help = "search every distinct score of a binary prediction for the best threshold: f1,
+            fbeta=B, youden, mcc, cost, precision-at-recall=R or recall-at-precision=P"
+        )]
+        optimize: Option<series::Objective>,
+
+        #[structopt(
+            long,
+            help = "the cost of each cell of the confusion matrix for --optimize cost, e.g.
+            'fp=5,fn=1', fp and fn default to 1, tp and tn to 0"
+        )]
+        cost: Option<series::Cost>,
+
+        #[structopt(
+            short,
+            long,
+            help = "the predicted and actual columns, in that order, e.g. -c 3,0"
+        )]
+        column: Option<series::Columns>,
+
+        #[structopt(flatten)]
+        input: InputOptions,
+
+        #[structopt(subcommand)]
+        mode: Option<EvalOptions>,
+    },
+
+    #[structopt(about = "data transformations and feature generation tools")]
+    Extract(ExtractOptions),
+
+    #[structopt(about = "very simple cli graphing")]
+    Graph(GraphOptions),
+}
+
+/// get_input will check if the input parameter is_some, and if so read input from a file, else,
+/// read input from stdin. A new String is returned with the contents.
+fn get_input(input: Option<PathBuf>) -> String {
     if let Some(path) = input {
-        match std::fs::read(path) {
-            Ok(bs) => bs,
+        match std::fs::read_to_string(path) {
+            Ok(s) => s,
             Err(_) => {
                 eprintln!("failed to read input file");
                 std::process::exit(1);
             }
         }
     } else {
-        let mut stdin = std::io::stdin();
-        let mut buf = vec![];
-        let _ = stdin.read_to_end(&mut buf);
-        buf
+        let mut input = String::new();
+        let stdin = std::io::stdin();
+
+        for line in stdin.lock().lines() {
+            let buf = line.expect("failed to read line");
+            input.push_str(&buf);
+            input.push('\n');
+        }
+
+        input
     }
 }
 
-fn entropy(bytes: &[u8]) -> f64 {
-    let mut histo: Vec<f64> = Vec::with_capacity(256);
+/// get_reader opens the input file, or stdin when no file is given, for reading a line at a
+/// time. Use this over get_input so large inputs are never held in memory as a whole.
+fn get_reader(input: Option<PathBuf>) -> Box<dyn BufRead> {
+    if let Some(path) = input {
+        match std::fs::File::open(path) {
+            Ok(f) => Box::new(std::io::BufReader::new(f)),
+            Err(_) => {
+                eprintln!("failed to read input file");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        Box::new(std::io::stdin().lock())
+    }
+}
+
+/// check_eval_mode exits when options of plain eval are given before a mode, as in
+/// st eval file.csv roc, a mode takes its input and its own options after its name
+fn check_eval_mode(cmd: &Command) {
+    let (threshold, verbose, bayes, bootstrap, optimize, cost, column, input, mode) = match cmd {
+        Command::Eval {
+            threshold,
+            verbose,
+            bayes,
+            bootstrap,
+            ci,
+            seed,
+            optimize,
+            cost,
+            column,
+            input,
+            mode: Some(mode),
+        } => (
+            threshold,
+            *verbose > 0,
+            bayes,
+            *bootstrap > 0 || *ci != 0.95 || seed.is_some(),
+            optimize,
+            cost,
+            column,
+            input,
+            mode,
+        ),
+        _ => return,
+    };
+
+    let name = match mode {
+        EvalOptions::Roc { .. } => "roc",
+        EvalOptions::Softprob { .. } => "softprob",
+        EvalOptions::Regression { .. } => "regression",
+        EvalOptions::Calibration { .. } => "calibration",
+        EvalOptions::Compare { .. } => "compare",
+    };
+
+    let mut given = vec![];
+    for (flag, set) in [
+        ("-t", threshold.is_some()),
+        ("-v", verbose),
+        ("-b", bayes.is_some()),
+        ("--bootstrap, --ci or --seed", bootstrap),
+        ("--optimize", optimize.is_some()),
+        ("--cost", cost.is_some()),
+        ("-c", column.is_some()),
+    ] {
+        if set {
+            given.push(flag);
+        }
+    }
+    given.extend(input.given());
+
+    if !given.is_empty() {
+        eprintln!(
+            "{} given before {}, the input and options of eval {} go after it",
+            given.join(", "),
+            name,
+            name
+        );
+        std::process::exit(1);
+    }
+}
+
+/// or_exit returns the value of a result from the series or xgb crates, or prints the error
+/// and exits
+fn or_exit<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// warn_missing reports the number of missing values on stderr, if there were any
+fn warn_missing(counts: &series::MissingCounts, missing: series::Missing) {
+    if let Some(warning) = counts.warning(missing) {
+        eprintln!("warning: {}", warning);
+    }
+}
+
+fn get_input_bytes(input: Option<PathBuf>) -> Vec<u8> {
+    if let Some(path) = input {
+        match std::fs::read(path) {
+            Ok(bs) => bs,
+            Err(_) => {
+                eprintln!("failed to read input file");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let mut stdin = std::io::stdin();
+        let mut buf = vec![];
+        let _ = stdin.read_to_end(&mut buf);
+        buf
+    }
+}
+
+fn entropy(bytes: &[u8]) -> f64 {
+    let mut histo: Vec<f64> = Vec::with_capacity(256);
 
     for _ in 0..256 {
         histo.push(0.0)
@@ -269,238 +1135,2586 @@ fn entropy(bytes: &[u8]) -> f64 {
             continue;
         }
 
-        let freq = (n as f64) / len;
-        out -= freq * freq.log(2.0);
-    }
+        let freq = n / len;
+        out -= freq * freq.log(2.0);
+    }
+
+    out
+}
+
+/// parse_probabilities parses the -p list of quintiles, or returns the k-quantiles without one
+fn parse_probabilities(probabilities: Option<String>, k: usize) -> Vec<f64> {
+    match probabilities {
+        Some(s) => match series::str_to_vector::<f64>(&s, ",") {
+            Ok(ps) if ps.iter().all(|p| (0.0..=1.0).contains(p)) => ps,
+            _ => {
+                eprintln!("probabilities must be a list of values in [0, 1]");
+                std::process::exit(1);
+            }
+        },
+        None => series::k_quantiles(k),
+    }
+}
+
+fn read_sketch(path: &Path) -> series::TDigest {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("failed to read sketch {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    match text.parse() {
+        Ok(digest) => digest,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn write_sketch(path: &Path, digest: &series::TDigest) {
+    if let Err(e) = std::fs::write(path, digest.to_string()) {
+        eprintln!("failed to write sketch {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+}
+
+fn read_calibrator(path: &Path) -> series::Calibrator {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("failed to read calibrator {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    match text.parse() {
+        Ok(calibrator) => calibrator,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// read_params reads xgb train parameters from a json object keyed by their xgboost names,
+/// parameters that aren't given keep their defaults
+fn read_params(path: &Path) -> xgb::Params {
+    let exit = |message: String| -> ! {
+        eprintln!("{}: {}", path.display(), message);
+        std::process::exit(1);
+    };
+
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("failed to read params {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let object = match serde_json::from_str::<Value>(&text) {
+        Ok(Value::Object(object)) => object,
+        Ok(_) => exit("expected a json object".to_string()),
+        Err(e) => exit(e.to_string()),
+    };
+
+    let mut params = xgb::Params::default();
+
+    for (key, value) in object {
+        if let Err(e) = set_param(&mut params, &key, &value) {
+            exit(e);
+        }
+    }
+
+    params
+}
+
+/// set_param sets a parameter of xgb by its XGBoost name, as in a --params file
+fn set_param(params: &mut xgb::Params, key: &str, value: &Value) -> Result<(), String> {
+    let float = || match value.as_f64() {
+        Some(x) => Ok(x as f32),
+        None => Err(format!("{} must be a number", key)),
+    };
+    let whole = || match value.as_u64() {
+        Some(x) if x <= u32::MAX as u64 => Ok(x as u32),
+        _ => Err(format!("{} must be a whole number", key)),
+    };
+    let string = || match value.as_str() {
+        Some(s) => Ok(s),
+        None => Err(format!("{} must be a string", key)),
+    };
+
+    match key {
+        "max_depth" => params.max_depth = whole()?,
+        "eta" => params.eta = float()?,
+        "subsample" => params.subsample = float()?,
+        "colsample_bytree" => params.colsample_bytree = float()?,
+        "colsample_bylevel" => params.colsample_bylevel = float()?,
+        "min_child_weight" => params.min_child_weight = float()?,
+        "gamma" => params.gamma = float()?,
+        "lambda" => params.lambda = float()?,
+        "alpha" => params.alpha = float()?,
+        "max_delta_step" => params.max_delta_step = float()?,
+        "scale_pos_weight" => params.scale_pos_weight = float()?,
+        "max_bin" => params.max_bin = whole()?,
+        "base_score" => params.base_score = float()?,
+        "tweedie_variance_power" => params.tweedie_variance_power = Some(float()?),
+        "huber_slope" => params.huber_slope = Some(float()?),
+        "num_round" => params.rounds = whole()?,
+        "early_stopping_rounds" => params.early_stopping_rounds = Some(whole()?),
+        "eval_metric" => params.eval_metric = Some(string()?.parse()?),
+        "seed" => match value.as_u64() {
+            Some(seed) => params.seed = seed,
+            None => return Err("seed must be a whole number".to_string()),
+        },
+        "tree_method" => params.tree_method = string()?.parse()?,
+        "colsample_bynode" => {
+            return Err(
+                "colsample_bynode needs xgboost 0.82 or later, the bundled xgboost is 0.81"
+                    .to_string(),
+            )
+        }
+        "objective" | "num_class" => {
+            return Err(format!(
+                "{} is a flag, -o for the objective and -n for num_class",
+                key
+            ))
+        }
+        _ => return Err(format!("unknown or unsupported parameter {}", key)),
+    }
+
+    Ok(())
+}
+
+/// params_json is the inverse of read_params, every parameter keyed by its XGBoost name
+fn params_json(params: &xgb::Params) -> Value {
+    let mut object = serde_json::Map::new();
+    let mut insert = |key: &str, value: Value| {
+        object.insert(key.to_string(), value);
+    };
+    // through the shortest string of an f32, so 0.3 isn't written as 0.30000001192092896
+    let float = |x: f32| Value::from(x.to_string().parse::<f64>().unwrap());
+
+    insert("max_depth", params.max_depth.into());
+    insert("eta", float(params.eta));
+    insert("subsample", float(params.subsample));
+    insert("colsample_bytree", float(params.colsample_bytree));
+    insert("colsample_bylevel", float(params.colsample_bylevel));
+    insert("min_child_weight", float(params.min_child_weight));
+    insert("gamma", float(params.gamma));
+    insert("lambda", float(params.lambda));
+    insert("alpha", float(params.alpha));
+    insert("max_delta_step", float(params.max_delta_step));
+    insert("scale_pos_weight", float(params.scale_pos_weight));
+    insert("tree_method", params.tree_method.to_string().into());
+    insert("max_bin", params.max_bin.into());
+    insert("base_score", float(params.base_score));
+    insert("seed", params.seed.into());
+    insert("num_round", params.rounds.into());
+
+    if let Some(power) = params.tweedie_variance_power {
+        insert("tweedie_variance_power", float(power));
+    }
+    if let Some(slope) = params.huber_slope {
+        insert("huber_slope", float(slope));
+    }
+    if let Some(metric) = params.eval_metric {
+        insert("eval_metric", metric.to_string().into());
+    }
+    if let Some(rounds) = params.early_stopping_rounds {
+        insert("early_stopping_rounds", rounds.into());
+    }
+
+    Value::Object(object)
+}
+
+/// ranking checks that a rank objective of xgb has a group column, and that only they do
+fn ranking(objective: &str, group: Option<usize>) -> bool {
+    let ranking = objective.starts_with("rank:");
+
+    if ranking && group.is_none() {
+        eprintln!("{} needs a group column, given with -g", objective);
+        std::process::exit(1);
+    }
+
+    if !ranking && group.is_some() {
+        eprintln!("-g is only used by rank objectives");
+        std::process::exit(1);
+    }
+
+    ranking
+}
+
+/// encode_labels encodes the classes of an xgb classifier as 0..N and returns the labels,
+/// which are saved with the model, and the number of classes. Every other objective, e.g.
+/// the relevance of a ranking, needs numbers.
+fn encode_labels(
+    objective: &str,
+    ydata: &mut [f32],
+    labels: Option<series::Labels>,
+    nclasses: u32,
+) -> (Option<series::Labels>, u32) {
+    let labels = if !objective.starts_with("binary:") && !objective.starts_with("multi:") {
+        if labels.is_some() {
+            eprintln!("{} needs numeric labels", objective);
+            std::process::exit(1);
+        }
+
+        None
+    } else {
+        let labels = match labels {
+            Some(labels) => labels,
+            None if objective == "binary:logistic"
+                && ydata.iter().all(|y| *y == 0.0 || *y == 1.0) =>
+            {
+                "0\n1".parse().unwrap()
+            }
+            None => series::Labels::encode(ydata),
+        };
+
+        Some(labels)
+    };
+
+    let nclasses = match &labels {
+        Some(labels) if objective == "binary:logistic" && labels.len() > 2 => {
+            eprintln!("binary:logistic needs two classes, found {}", labels.len());
+            std::process::exit(1);
+        }
+        Some(labels) if objective.starts_with("multi:") && nclasses == 1 => labels.len() as u32,
+        Some(labels) if objective.starts_with("multi:") && labels.len() > nclasses as usize => {
+            eprintln!(
+                "found {} classes, more than the {} given with -n",
+                labels.len(),
+                nclasses
+            );
+            std::process::exit(1);
+        }
+        _ => nclasses,
+    };
+
+    (labels, nclasses)
+}
+
+/// Rows are the features, labels and group sizes of some of the rows of xgb train or cv
+type Rows = (Vec<Vec<f64>>, Vec<f32>, Vec<u32>);
+
+/// units returns the sizes of the units rows are split by, which keeps the rows of a group
+/// together, without groups each row is a unit
+fn units(rows: usize, groups: &[u32]) -> Vec<u32> {
+    if groups.is_empty() {
+        vec![1; rows]
+    } else {
+        groups.to_vec()
+    }
+}
+
+/// split splits rows by unit into those that aren't held and those that are. Rows keep
+/// their order so the rows of a group stay contiguous.
+fn split(xdata: &[Vec<f64>], ydata: &[f32], groups: &[u32], held: &[bool]) -> (Rows, Rows) {
+    let mut kept = (vec![], vec![], vec![]);
+    let mut out = (vec![], vec![], vec![]);
+    let mut row = 0;
+
+    for (unit, size) in units(xdata.len(), groups).iter().enumerate() {
+        let set = if held[unit] { &mut out } else { &mut kept };
+
+        for i in row..row + *size as usize {
+            set.0.push(xdata[i].clone());
+            set.1.push(ydata[i]);
+        }
+
+        if !groups.is_empty() {
+            set.2.push(*size);
+        }
+
+        row += *size as usize;
+    }
+
+    (kept, out)
+}
+
+/// holdout splits the rows of xgb train into a training and a validation set, holding out
+/// a random fraction of the rows, or of the groups when there are groups
+fn holdout(
+    xdata: &[Vec<f64>],
+    ydata: &[f32],
+    groups: &[u32],
+    frac: f64,
+    seed: u64,
+) -> (Rows, Rows) {
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    let n = units(xdata.len(), groups).len();
+
+    if n < 2 {
+        eprintln!("--valid-frac needs at least two rows, or groups with -g");
+        std::process::exit(1);
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(&mut rand::rngs::StdRng::seed_from_u64(seed));
+
+    let count = ((n as f64 * frac).round() as usize).clamp(1, n - 1);
+    let mut held = vec![false; n];
+    for unit in &order[..count] {
+        held[*unit] = true;
+    }
+
+    split(xdata, ydata, groups, &held)
+}
+
+/// assign_folds returns the fold of each unit of xgb cv, dealt in a random order. Stratified,
+/// the rows of each class are dealt in turn so every fold gets its share of each class.
+fn assign_folds(ydata: &[f32], groups: &[u32], k: usize, stratify: bool, seed: u64) -> Vec<usize> {
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let n = units(ydata.len(), groups).len();
+
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(&mut rng);
+
+    // without groups each unit is a row, so its class is its label
+    if stratify {
+        order.sort_by(|a, b| ydata[*a].partial_cmp(&ydata[*b]).unwrap());
+    }
+
+    let mut folds = vec![0; n];
+    for (i, unit) in order.into_iter().enumerate() {
+        folds[unit] = i % k;
+    }
+
+    folds
+}
+
+/// mean_std returns the mean and population standard deviation of the scores of the folds
+fn mean_std(xs: &[f32]) -> (f64, f64) {
+    let n = xs.len() as f64;
+    let mean = xs.iter().map(|x| *x as f64).sum::<f64>() / n;
+    let var = xs.iter().map(|x| (*x as f64 - mean).powi(2)).sum::<f64>() / n;
+    (mean, var.sqrt())
+}
+
+/// write_eval_log writes the metric of each round of xgb train as csv
+fn write_eval_log(path: &Path, history: &xgb::History) {
+    let mut out = format!("round,train-{}", history.metric);
+    if !history.valid.is_empty() {
+        out.push_str(&format!(",valid-{}", history.metric));
+    }
+    out.push('\n');
+
+    for (round, train) in history.train.iter().enumerate() {
+        out.push_str(&format!("{},{}", round, train));
+        if let Some(valid) = history.valid.get(round) {
+            out.push_str(&format!(",{}", valid));
+        }
+        out.push('\n');
+    }
+
+    if let Err(e) = std::fs::write(path, out) {
+        eprintln!("failed to write {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+}
+
+/// join_record writes cells back with the delimiter they were read with, quoting csv fields
+/// as needed
+fn join_record(cells: &[String], delimiter: series::Delimiter) -> String {
+    match delimiter {
+        series::Delimiter::Tsv => cells.join("\t"),
+        series::Delimiter::Whitespace => cells.join(" "),
+        series::Delimiter::Csv(d) => {
+            let quoted: Vec<String> = cells
+                .iter()
+                .map(|c| {
+                    if c.contains(d) || c.contains('"') || c.contains('\n') || c.contains('\r') {
+                        format!("\"{}\"", c.replace('"', "\"\""))
+                    } else {
+                        c.to_string()
+                    }
+                })
+                .collect();
+
+            quoted.join(&d.to_string())
+        }
+    }
+}
+
+/// print_quintiles prints the value of each quantile, with the probability as a percent
+fn print_quintiles(probabilities: &[f64], values: &[f64], format: Format) {
+    if format != Format::Table {
+        let mut section = Section::new("quintiles", &["quantile", "value"]);
+
+        for (p, v) in probabilities.iter().zip(values) {
+            section.push(vec![Value::from(*p), Value::from(*v)]);
+        }
+
+        print!("{}", report::render(format, &[section]));
+        return;
+    }
+
+    for (p, v) in probabilities.iter().zip(values) {
+        // rounded so 0.1 is 10% rather than 10.000000000000002%
+        let perc_format = format!("{}%", (p * 100.0 * 1e6).round() / 1e6);
+        println!("{:<8} {:<8}", perc_format, *v as f32);
+    }
+}
+
+/// numeric_columns peeks at the first row of the input to select the columns holding numbers,
+/// so text columns such as a class label are left out of a summary. The peeked lines are put
+/// back in front of the returned reader. None is returned when every column is numeric.
+fn numeric_columns(
+    mut reader: Box<dyn BufRead>,
+    with_header: bool,
+    delimiter: series::Delimiter,
+) -> (Box<dyn BufRead>, Option<series::Columns>) {
+    let mut peeked = String::new();
+    let mut first = String::new();
+
+    for _ in 0..if with_header { 2 } else { 1 } {
+        first.clear();
+        if reader.read_line(&mut first).unwrap_or(0) == 0 {
+            break;
+        }
+        peeked.push_str(&first);
+    }
+
+    let selection = match series::Records::new(first.as_bytes(), delimiter).next() {
+        Some(Ok(cells)) if cells.len() > 1 => {
+            let numeric: Vec<series::Column> = cells
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| series::is_missing(c) || c.trim().parse::<f64>().is_ok())
+                .map(|(i, _)| series::Column::Index(i))
+                .collect();
+
+            if numeric.len() < cells.len() && !numeric.is_empty() {
+                Some(series::Columns(numeric))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    (Box::new(Cursor::new(peeked).chain(reader)), selection)
+}
+
+/// percentile_label names the column of a percentile given as a probability, rounded so
+/// -p 7 is p7 rather than the p7.000000000000001 of 0.07 * 100
+fn percentile_label(p: f64) -> String {
+    format!("p{}", (p * 100.0 * 1e9).round() / 1e9)
+}
+
+/// print_summary prints one row of stats per column, or one column per column transposed
+fn print_summary(names: &[String], summaries: &[series::Summary], transpose: bool, format: Format) {
+    let mut columns: Vec<String> = [
+        "column", "n", "missing", "min", "max", "mean", "median", "sd", "var", "skewness",
+        "kurtosis",
+    ]
+    .iter()
+    .map(|c| c.to_string())
+    .collect();
+
+    if let Some(summary) = summaries.first() {
+        columns.extend(
+            summary
+                .percentiles
+                .iter()
+                .map(|(p, _)| percentile_label(*p)),
+        );
+    }
+
+    let mut rows = vec![];
+    for (name, summary) in names.iter().zip(summaries) {
+        let mut row = vec![
+            Value::from(name.as_str()),
+            Value::from(summary.n),
+            Value::from(summary.missing),
+            Value::from(summary.min),
+            Value::from(summary.max),
+            Value::from(summary.mean),
+            Value::from(summary.median),
+            Value::from(summary.stdev),
+            Value::from(summary.var),
+            Value::from(summary.skewness),
+            Value::from(summary.kurtosis),
+        ];
+        row.extend(summary.percentiles.iter().map(|(_, v)| Value::from(*v)));
+        rows.push(row);
+    }
+
+    let mut section = Section::new("summary", &[]);
+
+    // transposed there is a row per stat and a column per input column
+    if transpose && format == Format::Table {
+        section.columns = vec!["-".to_string()];
+        section.columns.extend(names.iter().cloned());
+
+        for (index, stat) in columns.iter().enumerate().skip(1) {
+            let mut line = vec![Value::from(stat.as_str())];
+            line.extend(rows.iter().map(|row| row[index].clone()));
+            section.push(line);
+        }
+    } else {
+        section.columns = columns;
+        section.rows = rows;
+    }
+
+    print!("{}", report::render(format, &[section]));
+}
+
+/// label_value is a class label for the machine readable formats, a number when it is one
+fn label_value(name: &str) -> Value {
+    if let Ok(i) = name.parse::<i64>() {
+        Value::from(i)
+    } else if let Ok(x) = name.parse::<f64>() {
+        Value::from(x)
+    } else {
+        Value::from(name)
+    }
+}
+
+/// read_softprob reads rows of class probabilities followed by the actual label and returns
+/// them with the class names. A single probability column is the probability of the second
+/// class of a binary prediction.
+fn read_softprob<R: BufRead>(
+    rows: &mut series::Rows<R>,
+    missing: series::Missing,
+) -> (Vec<(Vec<f64>, usize)>, Vec<String>) {
+    let mut data: Vec<(Vec<f64>, usize)> = vec![];
+    let mut labels = series::Labels::default();
+
+    while let Some(mut cells) = or_exit(rows.next_labels()) {
+        let actual = cells.pop().unwrap_or_default();
+        let row: Vec<f64> = match cells.iter().map(|c| c.parse()).collect() {
+            Ok(row) => row,
+            Err(_) => {
+                eprintln!("error converting to float at line {}", rows.line());
+                std::process::exit(1);
+            }
+        };
+
+        if row.is_empty() {
+            eprintln!("expected probability columns and an actual column");
+            std::process::exit(1);
+        }
+
+        if let Some((first, _)) = data.first() {
+            if first.len() != row.len() {
+                eprintln!(
+                    "expected {} probability columns, found {}",
+                    first.len(),
+                    row.len()
+                );
+                std::process::exit(1);
+            }
+        }
+
+        data.push((row, labels.push(&actual)));
+    }
+
+    warn_missing(rows.missing_counts(), missing);
+
+    if data.is_empty() {
+        eprintln!("input must be a non empty set");
+        std::process::exit(1);
+    }
+
+    // a numeric class is the index of its column, other labels are matched to the columns in
+    // sorted order, so every class must be present
+    let k = data[0].0.len().max(2);
+    let classes: Option<Vec<usize>> = labels
+        .names()
+        .iter()
+        .map(|n| n.parse().ok().filter(|c| *c < k))
+        .collect();
+
+    let names: Vec<String> = match classes {
+        Some(classes) => {
+            for (_, actual) in data.iter_mut() {
+                *actual = classes[*actual];
+            }
+
+            (0..k).map(|i| i.to_string()).collect()
+        }
+        None if labels.len() == k => {
+            let order = labels.sort();
+            for (_, actual) in data.iter_mut() {
+                *actual = order[*actual];
+            }
+
+            labels.names().to_vec()
+        }
+        None => {
+            eprintln!(
+                "found {} labels for {} classes, labels must be classes 0..{} or every class must be present",
+                labels.len(),
+                k,
+                k - 1
+            );
+            std::process::exit(1);
+        }
+    };
+
+    (data, names)
+}
+
+/// confusion_section is the confusion matrix in long form, one row per cell
+fn confusion_section(matrix: &[Vec<u32>], names: &[String]) -> Section {
+    let size = matrix.len();
+
+    // the rows of the matrix are in descending class order
+    let mut confusion = Section::new("confusion_matrix", &["predicted", "actual", "count"]);
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, count) in row.iter().enumerate() {
+            confusion.push(vec![
+                label_value(&names[size - 1 - i]),
+                label_value(&names[size - 1 - j]),
+                Value::from(*count),
+            ]);
+        }
+    }
+
+    confusion
+}
+
+/// print_confusion_matrix prints the confusion matrix of eval, with the rows in descending
+/// class order. The names are the class labels in ascending order.
+fn print_confusion_matrix(matrix: &[Vec<u32>], names: &[String]) {
+    let size = matrix.len();
+
+    // convert the matrix into a formatted string for stdout
+    let mut header = String::new();
+    let mut body = String::new();
+    header.push_str("Confusion Matrix\n");
+    header.push_str("Predicted on y-axis, Actual on x-axis\n");
+    header.push('\n');
+    header.push_str(&format!("{:<8}", "-"));
+
+    for (i, row) in matrix.iter().enumerate() {
+        header.push_str(&format!("{:<8}", names[size - 1 - i]));
+
+        body.push_str(&format!("{:<8}", names[size - 1 - i]));
+
+        for count in row {
+            body.push_str(&format!("{:<8}", count));
+        }
+
+        body.push('\n');
+    }
+
+    // print matrix to stdout
+    println!("{}", header);
+    println!("{}", body);
+}
+
+/// eval_sections builds the eval report for the machine readable formats. Unlike the table
+/// it always holds the per class stats, the threshold table of a binary prediction is only
+/// given with -vv as in the table.
+fn eval_sections(
+    matrix: &[Vec<u32>],
+    names: &[String],
+    stats: &[series::CMatrixStats],
+    bases: &[f32],
+    table: Option<&series::ThresholdCounts>,
+) -> Vec<Section> {
+    let size = matrix.len();
+    let confusion = confusion_section(matrix, names);
+
+    let mut bayes = Section::new("bayes", &["class", "probability"]);
+
+    for stat in stats {
+        if !bases.is_empty() {
+            let base = bases[stat.label];
+            let prob_positive = (stat.tpr * base) + (stat.fpr * (1.0 - base));
+
+            bayes.push(vec![
+                label_value(&names[stat.label]),
+                report::from_f32((stat.tpr * base) / prob_positive),
+            ]);
+        }
+    }
+
+    let mut sections = vec![confusion];
+    sections.extend(class_sections(matrix, names, stats));
+
+    if !bases.is_empty() {
+        sections.push(bayes);
+    }
+
+    if let (Some(table), 2) = (table, size) {
+        let mut thresholds = Section::new(
+            "thresholds",
+            &["threshold", "precision", "tpr", "f1", "fpr"],
+        );
+
+        for row in table.stats() {
+            thresholds.push(row.into_iter().map(report::from_f32).collect());
+        }
+
+        sections.push(thresholds);
+    }
+
+    sections
+}
+
+/// class_sections reports the one-vs-rest stats of each class, their macro and support
+/// weighted averages, and the stats of the whole matrix
+fn class_sections(
+    matrix: &[Vec<u32>],
+    names: &[String],
+    stats: &[series::CMatrixStats],
+) -> Vec<Section> {
+    const METRICS: [&str; 9] = [
+        "precision",
+        "tpr",
+        "fpr",
+        "tnr",
+        "fnr",
+        "npv",
+        "f1",
+        "mcc",
+        "kappa",
+    ];
+
+    let values = |stat: &series::CMatrixStats| {
+        [
+            stat.precision,
+            stat.tpr,
+            stat.fpr,
+            stat.tnr,
+            stat.fnr,
+            stat.npv,
+            stat.f1,
+            stat.mcc,
+            stat.kappa,
+        ]
+    };
+
+    let mut columns = vec!["class", "support"];
+    columns.extend(METRICS);
+    let mut classes = Section::new("classes", &columns);
+
+    for stat in stats {
+        let mut row = vec![label_value(&names[stat.label]), Value::from(stat.support)];
+        row.extend(values(stat).into_iter().map(report::from_f32));
+        classes.push(row);
+    }
+
+    let mut columns = vec!["average"];
+    columns.extend(METRICS);
+    let mut averages = Section::new("averages", &columns);
+
+    // undefined stats, e.g. the precision of a class never predicted, are left out
+    for (name, weighted) in [("macro", false), ("weighted", true)] {
+        let mut row = vec![Value::from(name)];
+
+        for i in 0..METRICS.len() {
+            let (mut sum, mut total) = (0.0, 0.0);
+
+            for stat in stats {
+                let x = values(stat)[i];
+                let w = if weighted { stat.support as f32 } else { 1.0 };
+
+                if !x.is_nan() {
+                    sum += x * w;
+                    total += w;
+                }
+            }
+
+            row.push(report::from_f32(sum / total));
+        }
+
+        averages.push(row);
+    }
+
+    let summary = series::confusion_matrix_summary(matrix);
+    let mut overall = Section::new(
+        "overall",
+        &["n", "accuracy", "balanced_accuracy", "kappa", "mcc"],
+    );
+
+    overall.push(vec![
+        Value::from(summary.n),
+        report::from_f32(summary.accuracy),
+        report::from_f32(summary.balanced_accuracy),
+        report::from_f32(summary.kappa),
+        report::from_f32(summary.mcc),
+    ]);
+
+    vec![classes, averages, overall]
+}
+
+/// optimum_section reports the threshold chosen by eval --optimize, a score at or above it is
+/// predicted to be the second class
+fn optimum_section(optimum: &series::Optimum, objective: series::Objective) -> Section {
+    let mut section = Section::new(
+        "optimize",
+        &[
+            "objective",
+            "threshold",
+            "value",
+            "tp",
+            "fp",
+            "fn",
+            "tn",
+            "precision",
+            "recall",
+        ],
+    );
+
+    let (tp, fp, fn_) = (optimum.tp as f64, optimum.fp as f64, optimum.fn_ as f64);
+
+    section.push(vec![
+        Value::from(objective.to_string()),
+        Value::from(optimum.threshold),
+        Value::from(optimum.value),
+        Value::from(optimum.tp),
+        Value::from(optimum.fp),
+        Value::from(optimum.fn_),
+        Value::from(optimum.tn),
+        Value::from(tp / (tp + fp)),
+        Value::from(tp / (tp + fn_)),
+    ]);
+
+    section
+}
+
+/// bootstrap_section resamples the (predicted, actual, score) rows of eval, with classes in
+/// sorted order, and reports a percentile interval for the stats of each class, of the whole
+/// matrix, and for the AUC of a binary prediction with numeric scores
+fn bootstrap_section(
+    rows: &[(usize, usize, Option<f64>)],
+    names: &[String],
+    resamples: usize,
+    ci: f64,
+    seed: Option<u64>,
+) -> Section {
+    let size = names.len();
+    let auc = size == 2 && rows.iter().all(|(_, _, score)| score.is_some());
+
+    // every metric of a set of rows, the stats of each class, of the matrix and then the AUC
+    let metrics = |indices: &mut dyn Iterator<Item = usize>| -> Vec<f64> {
+        let mut matrix = vec![vec![0; size]; size];
+        let mut scores = vec![];
+
+        for i in indices {
+            let (predicted, actual, score) = rows[i];
+            matrix[size - 1 - predicted][size - 1 - actual] += 1;
+
+            if let Some(score) = score {
+                scores.push((score, actual == 1));
+            }
+        }
+
+        let mut out = vec![];
+        for stat in series::confusion_matrix_stats(&matrix) {
+            out.extend(
+                [
+                    stat.precision,
+                    stat.tpr,
+                    stat.fpr,
+                    stat.tnr,
+                    stat.fnr,
+                    stat.npv,
+                    stat.f1,
+                    stat.mcc,
+                    stat.kappa,
+                ]
+                .map(|x| x as f64),
+            );
+        }
+
+        let summary = series::confusion_matrix_summary(&matrix);
+        out.extend(
+            [
+                summary.accuracy,
+                summary.balanced_accuracy,
+                summary.kappa,
+                summary.mcc,
+            ]
+            .map(|x| x as f64),
+        );
+
+        if auc {
+            out.push(series::roc(&scores).auc);
+        }
+
+        out
+    };
+
+    let estimates = metrics(&mut (0..rows.len()));
+    let mut resampled = vec![vec![]; estimates.len()];
+
+    let mut sampler = bootstrap::Bootstrap::new(rows.len(), seed);
+    let mut indices = vec![];
+
+    for _ in 0..resamples {
+        sampler.sample(&mut indices);
+
+        for (i, x) in metrics(&mut indices.iter().copied())
+            .into_iter()
+            .enumerate()
+        {
+            resampled[i].push(x);
+        }
+    }
+
+    let mut keys = vec![];
+    for name in names {
+        for metric in [
+            "precision",
+            "tpr",
+            "fpr",
+            "tnr",
+            "fnr",
+            "npv",
+            "f1",
+            "mcc",
+            "kappa",
+        ] {
+            keys.push((Some(name), metric));
+        }
+    }
+
+    // the stats of the whole matrix have no class
+    for metric in ["accuracy", "balanced_accuracy", "kappa", "mcc"] {
+        keys.push((None, metric));
+    }
+
+    // the AUC is of the second class, as with binary:logistic
+    if auc {
+        keys.push((Some(&names[1]), "auc"));
+    }
+
+    let mut section = Section::new(
+        "bootstrap",
+        &["class", "metric", "estimate", "lower", "upper"],
+    );
+
+    for ((name, metric), (estimate, xs)) in keys.into_iter().zip(estimates.iter().zip(&resampled)) {
+        let (lower, upper) = bootstrap::interval(xs, ci);
+
+        section.push(vec![
+            name.map(|n| label_value(n)).unwrap_or(Value::Null),
+            Value::from(metric),
+            Value::from(*estimate),
+            Value::from(lower),
+            Value::from(upper),
+        ]);
+    }
+
+    section
+}
+
+/// check_canvas exits when a line or scatter graph has no room to draw in
+fn check_canvas(width: usize, height: usize) {
+    if width == 0 || height == 0 {
+        eprintln!("the width and height of a graph must be at least 1");
+        std::process::exit(1);
+    }
+}
+
+/// to_points reads one or two columns into (x, y) pairs for graphing. A single column is
+/// plotted against its row index, points with a missing value are left out.
+fn to_points(
+    reader: Box<dyn BufRead>,
+    with_header: bool,
+    delimiter: series::Delimiter,
+    missing: series::Missing,
+) -> Vec<(f64, f64)> {
+    let matrix = or_exit(series::to_matrix(
+        reader,
+        1000000,
+        with_header,
+        None,
+        delimiter,
+        missing,
+    ));
+    warn_missing(&matrix.missing, missing);
+
+    let points: Vec<(f64, f64)> = matrix
+        .xdata
+        .iter()
+        .enumerate()
+        .map(|(index, row)| match row.len() {
+            1 => (index as f64, row[0]),
+            2 => (row[0], row[1]),
+            n => {
+                eprintln!("expected one or two columns, found {} at row {}", n, index);
+                std::process::exit(1);
+            }
+        })
+        .filter(|(x, y): &(f64, f64)| !x.is_nan() && !y.is_nan())
+        .collect();
+
+    if points.is_empty() {
+        eprintln!("input must be a non empty set");
+        std::process::exit(1);
+    }
+
+    points
+}
+
+/// calculates and normalizes the byte histogram
+fn to_byte_histogram(bytes: &[u8]) -> Vec<f64> {
+    let mut histo: Vec<f64> = Vec::with_capacity(256);
+
+    for _ in 0..256 {
+        histo.push(0.0)
+    }
+
+    bytes.iter().for_each(|b| {
+        histo[*b as usize] += 1.0;
+    });
+
+    let sum = bytes.len() as f64;
+
+    histo.iter().map(|x| x / sum).collect()
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let format = opt.format;
+    check_eval_mode(&opt.cmd);
+
+    match opt.cmd {
+        Command::Summary {
+            transpose,
+            column,
+            percentiles,
+            approx,
+            exact_limit,
+            compression,
+            input,
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            let percentiles: Vec<f64> = match series::str_to_vector(&percentiles, ",") {
+                Ok(ps) if ps.iter().all(|p| (0.0..=100.0).contains(p)) => ps,
+                _ => {
+                    eprintln!("percentiles must be a list of values in [0, 100]");
+                    std::process::exit(1);
+                }
+            };
+
+            // missing values are read as NaN so they can be counted per column, describe
+            // applies the policy
+            let read_as = if missing == series::Missing::Error {
+                missing
+            } else {
+                series::Missing::Nan
+            };
+
+            let mut reader = get_reader(input);
+            let mut column = column;
+
+            if column.is_none() {
+                let (peeked, numeric) = numeric_columns(reader, with_header, delimiter);
+                reader = peeked;
+                column = numeric;
+            }
+
+            let probabilities: Vec<f64> = percentiles.iter().map(|p| p / 100.0).collect();
+
+            // the moments are streamed, only the values for exact quantiles are kept
+            let limit = if approx { 0 } else { exact_limit };
+            let mut describe = series::Describe::new(missing, limit, compression);
+            let mut rows =
+                or_exit(series::Rows::new(reader, delimiter, with_header, column)).missing(read_as);
+
+            for row in &mut rows {
+                or_exit(describe.push(or_exit(row)));
+            }
+
+            if describe.estimated() && !approx {
+                eprintln!(
+                    "warning: more than {} values, the median and percentiles are estimated, raise --exact-limit for exact ones",
+                    exact_limit
+                );
+            }
+
+            let summaries = or_exit(describe.summaries(&probabilities));
+
+            print_summary(&rows.names(), &summaries, transpose, format);
+        }
+
+        Command::Quintiles {
+            quintiles,
+            probabilities,
+            typ,
+            approx,
+            compression,
+            sketch,
+            column,
+            input,
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            let probabilities = parse_probabilities(probabilities, quintiles);
+            let reader = get_reader(input);
+
+            if approx {
+                let (mut digest, counts) = or_exit(series::to_tdigest(
+                    reader,
+                    with_header,
+                    column.as_ref(),
+                    delimiter,
+                    missing,
+                    compression,
+                ));
+                warn_missing(&counts, missing);
+
+                if let Some(path) = sketch {
+                    write_sketch(&path, &digest);
+                }
+
+                if digest.count() == 0.0 {
+                    eprintln!("input must be a non empty set");
+                    std::process::exit(1);
+                }
+
+                let values: Vec<f64> = probabilities.iter().map(|p| digest.quantile(*p)).collect();
+                print_quintiles(&probabilities, &values, format);
+                return;
+            }
+
+            let (data, counts) = or_exit(series::to_vector(
+                reader,
+                with_header,
+                column.as_ref(),
+                delimiter,
+                missing,
+            ));
+            warn_missing(&counts, missing);
+
+            if data.is_empty() {
+                eprintln!("input must be a non empty set");
+                std::process::exit(1);
+            }
+
+            let values = series::quantiles(&data, &probabilities, typ);
+            print_quintiles(&probabilities, &values, format);
+        }
+
+        Command::Calibrate(CalibrateOptions::Fit {
+            method,
+            output,
+            column,
+            input,
+        }) => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            let mut rows = or_exit(series::Rows::new(
+                get_reader(input),
+                delimiter,
+                with_header,
+                column,
+            ))
+            .missing(missing);
+
+            let (data, _) = read_softprob(&mut rows, missing);
+
+            if data[0].0.len() != 1 {
+                eprintln!("expected a score and actual column");
+                std::process::exit(1);
+            }
+
+            let pairs: Vec<(f64, bool)> = data.iter().map(|(s, a)| (s[0], *a == 1)).collect();
+            let calibrator = series::Calibrator::fit(&pairs, method);
+
+            if let Err(e) = std::fs::write(&output, calibrator.to_string()) {
+                eprintln!("failed to write calibrator {}: {}", output.display(), e);
+                std::process::exit(1);
+            }
+
+            // the fit on its own input, a check rather than an estimate of the error
+            let calibrated: Vec<(f64, bool)> = pairs
+                .iter()
+                .map(|(s, a)| (calibrator.apply(*s), *a))
+                .collect();
+            let result = series::calibration(&calibrated, 10, series::Binning::Uniform);
+
+            let mut section = Section::new("calibrator", &["n", "brier", "ece"]);
+            section.push(vec![
+                Value::from(result.n),
+                Value::from(result.brier),
+                Value::from(result.ece),
+            ]);
+
+            print!("{}", report::render(format, &[section]));
+        }
+
+        Command::Calibrate(CalibrateOptions::Apply {
+            model_in,
+            column,
+            input,
+        }) => {
+            if input.missing.is_some() {
+                eprintln!("calibrate apply passes missing scores through, --missing is not used");
+                std::process::exit(1);
+            }
+            let (with_header, delimiter, _, input) = input.parts(series::Missing::Error);
+
+            let calibrator = read_calibrator(&model_in);
+            let mut records = series::Records::new(get_reader(input), delimiter);
+            let mut buf = String::new();
+
+            let header = if with_header {
+                or_exit(records.next().transpose())
+            } else {
+                None
+            };
+
+            if let Some(header) = &header {
+                buf.push_str(&join_record(header, delimiter));
+                buf.push('\n');
+            }
+
+            let mut selected = None;
+
+            while let Some(record) = records.next() {
+                let mut cells = or_exit(record);
+
+                let col = match selected {
+                    Some(col) => col,
+                    None => match column.resolve(header.as_deref(), cells.len()) {
+                        Ok(cols) if cols.len() == 1 => *selected.insert(cols[0]),
+                        Ok(_) => {
+                            eprintln!("expected a single score column");
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            std::process::exit(1);
+                        }
+                    },
+                };
+
+                match cells.get(col) {
+                    Some(cell) if series::is_missing(cell) => {}
+                    Some(cell) => match cell.trim().parse::<f64>() {
+                        Ok(score) => cells[col] = calibrator.apply(score).to_string(),
+                        Err(_) => {
+                            eprintln!(
+                                "error converting to float: {} at line {}",
+                                cell, records.line
+                            );
+                            std::process::exit(1);
+                        }
+                    },
+                    None => {
+                        eprintln!("missing column {} at line {}", col, records.line);
+                        std::process::exit(1);
+                    }
+                }
+
+                buf.push_str(&join_record(&cells, delimiter));
+                buf.push('\n');
+
+                if buf.len() > 64 * 1024 {
+                    std::io::stdout().write_all(buf.as_bytes()).unwrap();
+                    buf = String::new();
+                }
+            }
+
+            std::io::stdout().write_all(buf.as_bytes()).unwrap();
+        }
+
+        Command::Quantiles(QuantilesOptions::Merge {
+            quintiles,
+            probabilities,
+            output,
+            inputs,
+        }) => {
+            let probabilities = parse_probabilities(probabilities, quintiles);
+
+            let mut digest = read_sketch(&inputs[0]);
+            for path in &inputs[1..] {
+                digest.merge(&read_sketch(path));
+            }
+
+            if let Some(path) = output {
+                write_sketch(&path, &digest);
+            }
+
+            if digest.count() == 0.0 {
+                eprintln!("input must be a non empty set");
+                std::process::exit(1);
+            }
+
+            let values: Vec<f64> = probabilities.iter().map(|p| digest.quantile(*p)).collect();
+            print_quintiles(&probabilities, &values, format);
+        }
+
+        Command::Eval {
+            threshold,
+            verbose,
+            bayes,
+            bootstrap,
+            ci,
+            seed,
+            optimize,
+            cost,
+            column,
+            input,
+            mode: None,
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            if bootstrap > 0 && !(ci > 0.0 && ci < 1.0) {
+                eprintln!("--ci must be between 0 and 1");
+                std::process::exit(1);
+            }
+
+            // --cost on its own implies --optimize cost
+            let objective = match (optimize, cost) {
+                (None | Some(series::Objective::Cost(_)), Some(cost)) => {
+                    Some(series::Objective::Cost(cost))
+                }
+                (Some(_), Some(_)) => {
+                    eprintln!("--cost only applies to --optimize cost");
+                    std::process::exit(1);
+                }
+                (objective, None) => objective,
+            };
+
+            let bases: Vec<f32> = if let Some(s) = bayes {
+                match series::str_to_vector(&s, ",") {
+                    Ok(xs) => xs,
+                    Err(_) => {
+                        eprintln!("error parsing -b list");
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                vec![]
+            };
+
+            // read the tuples one at a time, only the counts are kept unless resampling
+            let mut samples = vec![];
+            let mut counts = series::ConfusionCounts::new(threshold);
+            let mut table = series::ThresholdCounts::default();
+            let mut rows = or_exit(series::Rows::new(
+                get_reader(input),
+                delimiter,
+                with_header,
+                column,
+            ))
+            .missing(missing);
+
+            // classes are labels, so they may be strings or sparse numbers
+            while let Some(row) = or_exit(rows.next_labels()) {
+                if row.len() != 2 {
+                    eprintln!(
+                        "expected a predicted and actual column, found {} columns",
+                        row.len()
+                    );
+                    std::process::exit(1);
+                }
+
+                let (predicted, actual) = or_exit(counts.push_labels(&row[0], &row[1]));
+
+                if bootstrap > 0 || objective.is_some() {
+                    samples.push((predicted, actual, row[0].parse::<f64>().ok()));
+                }
+
+                if verbose > 1 {
+                    if let (Ok(p), Ok(a)) = (row[0].parse(), row[1].parse()) {
+                        table.push(p, a);
+                    }
+                }
+            }
+
+            warn_missing(rows.missing_counts(), missing);
+
+            let names = counts.labels().names().to_vec();
+            let matrix = counts.matrix();
+            let stats = series::confusion_matrix_stats(&matrix);
+
+            let size = matrix.len();
+
+            if !bases.is_empty() && bases.len() != size {
+                eprintln!("invalid number of baseline values, it must match the number of classes");
+                std::process::exit(1);
+            }
+
+            let order = counts.order();
+            for (predicted, actual, _) in samples.iter_mut() {
+                *predicted = order[*predicted];
+                *actual = order[*actual];
+            }
+
+            let intervals = if bootstrap > 0 {
+                Some(bootstrap_section(&samples, &names, bootstrap, ci, seed))
+            } else {
+                None
+            };
+
+            let optimum = objective.map(|objective| {
+                let scores: Option<Vec<(f64, bool)>> = samples
+                    .iter()
+                    .map(|(_, actual, score)| score.map(|s| (s, *actual == 1)))
+                    .collect();
+
+                let scores = match scores {
+                    Some(scores) if size == 2 => scores,
+                    _ => {
+                        eprintln!("--optimize needs numeric scores and two classes");
+                        std::process::exit(1);
+                    }
+                };
+
+                match series::optimize(&series::roc(&scores), objective) {
+                    Some(optimum) => (optimum_section(&optimum, objective), optimum),
+                    None => {
+                        eprintln!("no threshold reaches {}", objective);
+                        std::process::exit(1);
+                    }
+                }
+            });
+
+            if format != Format::Table {
+                let table = (verbose > 1).then_some(&table);
+                let mut sections = eval_sections(&matrix, &names, &stats, &bases, table);
+                sections.extend(intervals);
+
+                if let Some((section, optimum)) = optimum {
+                    let mut confusion = confusion_section(&optimum.matrix(), &names);
+                    confusion.name = "optimized_confusion_matrix";
+
+                    sections.push(section);
+                    sections.push(confusion);
+                }
+
+                print!("{}", report::render(format, &sections));
+                return;
+            }
+
+            print_confusion_matrix(&matrix, &names);
+
+            let mut bayes_calc_str = String::new();
+
+            for stat in &stats {
+                if !bases.is_empty() {
+                    let prob_positive =
+                        (stat.tpr * bases[stat.label]) + (stat.fpr * (1.0 - bases[stat.label]));
+
+                    let prob_class_given_positive = (stat.tpr * bases[stat.label]) / prob_positive;
+
+                    bayes_calc_str.push_str(&format!(
+                        "{}: Pr(class_{}|positive) = {}\n",
+                        names[stat.label], names[stat.label], prob_class_given_positive
+                    ));
+                }
+            }
+
+            if verbose > 0 {
+                println!(
+                    "{}",
+                    report::render(format, &class_sections(&matrix, &names, &stats))
+                );
+            }
+
+            if !bases.is_empty() {
+                println!("Bayes estimates with baseline rates\n");
+                print!("{}", bayes_calc_str);
+                println!();
+            }
+
+            if verbose > 1 && matrix.len() == 2 {
+                let output = table.stats();
+
+                println!("ROC table\n");
+                println!("{:<8}{:<8}{:<8}{:<8}{:<8}", "t", "prec", "tpr", "f1", "fpr");
+
+                for row in output {
+                    println!(
+                        "{:<8.2}{:<8.4}{:<8.4}{:<8.4}{:<8.4}",
+                        row[0], row[1], row[2], row[3], row[4]
+                    );
+                }
+
+                println!();
+            }
+
+            if let (Some((section, optimum)), Some(objective)) = (optimum, objective) {
+                println!("Threshold {} for {}\n", optimum.threshold, objective);
+                print_confusion_matrix(&optimum.matrix(), &names);
+                print!("{}", report::render(format, &[section]));
+                println!();
+            }
+
+            if let Some(section) = intervals {
+                println!(
+                    "Bootstrap {}% intervals of {} resamples\n",
+                    ci * 100.0,
+                    bootstrap
+                );
+                print!("{}", report::render(format, &[section]));
+            }
+        }
+
+        Command::Eval {
+            mode:
+                Some(EvalOptions::Roc {
+                    column,
+                    curve,
+                    input,
+                }),
+            ..
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            let mut scores = vec![];
+            let mut rows = or_exit(series::Rows::new(
+                get_reader(input),
+                delimiter,
+                with_header,
+                column,
+            ))
+            .missing(missing);
+
+            for row in series::complete_rows(&mut rows) {
+                let row = or_exit(row);
+
+                if row.len() != 2 {
+                    eprintln!(
+                        "expected a score and actual column, found {} columns",
+                        row.len()
+                    );
+                    std::process::exit(1);
+                }
+
+                if row[1] != 0.0 && row[1] != 1.0 {
+                    eprintln!("roc needs binary labels, 0 or 1, found {}", row[1]);
+                    std::process::exit(1);
+                }
+
+                scores.push((row[0], row[1] == 1.0));
+            }
+
+            warn_missing(rows.missing_counts(), missing);
+
+            let roc = series::roc(&scores);
+
+            if roc.positives == 0 || roc.negatives == 0 {
+                eprintln!("roc needs both classes in the input");
+                std::process::exit(1);
+            }
+
+            if let Some(path) = curve {
+                let mut section = Section::new(
+                    "curve",
+                    &["threshold", "tp", "fp", "tpr", "fpr", "precision"],
+                );
+
+                for point in &roc.points {
+                    section.push(vec![
+                        Value::from(point.threshold),
+                        Value::from(point.tp),
+                        Value::from(point.fp),
+                        Value::from(point.tpr),
+                        Value::from(point.fpr),
+                        Value::from(point.precision),
+                    ]);
+                }
+
+                let curve_format = if format == Format::Table {
+                    Format::Csv
+                } else {
+                    format
+                };
+
+                if let Err(e) = std::fs::write(&path, report::render(curve_format, &[section])) {
+                    eprintln!("failed to write curve {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+
+            let mut section = Section::new(
+                "roc",
+                &[
+                    "n",
+                    "positives",
+                    "negatives",
+                    "roc_auc",
+                    "average_precision",
+                ],
+            );
+            section.push(vec![
+                Value::from(scores.len()),
+                Value::from(roc.positives),
+                Value::from(roc.negatives),
+                Value::from(roc.auc),
+                Value::from(roc.average_precision),
+            ]);
+
+            print!("{}", report::render(format, &[section]));
+        }
+
+        Command::Eval {
+            mode:
+                Some(EvalOptions::Softprob {
+                    column,
+                    top_k,
+                    input,
+                }),
+            ..
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            let mut rows = or_exit(series::Rows::new(
+                get_reader(input),
+                delimiter,
+                with_header,
+                column,
+            ))
+            .missing(missing);
+
+            let (data, names) = read_softprob(&mut rows, missing);
+
+            if names.len() != data[0].0.len() {
+                eprintln!("expected two or more probability columns and an actual column");
+                std::process::exit(1);
+            }
+
+            let result = series::multiclass(&data, top_k);
+
+            let mut summary = Section::new("summary", &["n", "classes", "log_loss"]);
+            summary.push(vec![
+                Value::from(result.n),
+                Value::from(result.classes.len()),
+                Value::from(result.log_loss),
+            ]);
+
+            let mut accuracy = Section::new("top_k", &["k", "accuracy"]);
+            for (k, acc) in &result.top_k {
+                accuracy.push(vec![Value::from(*k), Value::from(*acc)]);
+            }
+
+            let mut classes = Section::new(
+                "classes",
+                &["class", "support", "precision", "recall", "f1", "auc"],
+            );
+            for c in &result.classes {
+                classes.push(vec![
+                    label_value(&names[c.label]),
+                    Value::from(c.support),
+                    Value::from(c.precision),
+                    Value::from(c.recall),
+                    Value::from(c.f1),
+                    Value::from(c.auc),
+                ]);
+            }
+
+            let mut averages = Section::new("averages", &["average", "precision", "recall", "f1"]);
+            for a in &result.averages {
+                averages.push(vec![
+                    Value::from(a.name),
+                    Value::from(a.precision),
+                    Value::from(a.recall),
+                    Value::from(a.f1),
+                ]);
+            }
+
+            let sections = vec![summary, accuracy, classes, averages];
+
+            if format == Format::Table {
+                print_confusion_matrix(&result.matrix(), &names);
+                print!("{}", report::render(format, &sections));
+            } else {
+                let mut all = vec![confusion_section(&result.matrix(), &names)];
+                all.extend(sections);
+                print!("{}", report::render(format, &all));
+            }
+        }
+
+        Command::Eval {
+            mode:
+                Some(EvalOptions::Calibration {
+                    column,
+                    bins,
+                    binning,
+                    input,
+                }),
+            ..
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            let mut rows = or_exit(series::Rows::new(
+                get_reader(input),
+                delimiter,
+                with_header,
+                column,
+            ))
+            .missing(missing);
+
+            let (data, _) = read_softprob(&mut rows, missing);
+
+            let result = if data[0].0.len() == 1 {
+                let pairs: Vec<(f64, bool)> = data.iter().map(|(p, a)| (p[0], *a == 1)).collect();
+                series::calibration(&pairs, bins, binning)
+            } else {
+                series::calibration_multiclass(&data, bins, binning)
+            };
+
+            let mut summary = Section::new("calibration", &["n", "bins", "brier", "ece", "mce"]);
+            summary.push(vec![
+                Value::from(result.n),
+                Value::from(result.bins.len()),
+                Value::from(result.brier),
+                Value::from(result.ece),
+                Value::from(result.mce),
+            ]);
+
+            let mut bins = Section::new("bins", &["lower", "upper", "n", "predicted", "observed"]);
+            for bin in &result.bins {
+                bins.push(vec![
+                    Value::from(bin.lower),
+                    Value::from(bin.upper),
+                    Value::from(bin.n),
+                    Value::from(bin.predicted),
+                    Value::from(bin.observed),
+                ]);
+            }
+
+            print!("{}", report::render(format, &[summary, bins]));
+        }
+
+        Command::Eval {
+            mode:
+                Some(EvalOptions::Compare {
+                    column,
+                    threshold,
+                    bootstrap,
+                    ci,
+                    seed,
+                    input,
+                }),
+            ..
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            if bootstrap > 0 && !(ci > 0.0 && ci < 1.0) {
+                eprintln!("--ci must be between 0 and 1");
+                std::process::exit(1);
+            }
+
+            let mut rows = or_exit(series::Rows::new(
+                get_reader(input),
+                delimiter,
+                with_header,
+                column,
+            ))
+            .missing(missing);
+
+            let mut data = vec![];
+            while let Some(row) = or_exit(rows.next_labels()) {
+                if row.len() != 3 {
+                    eprintln!(
+                        "expected pred_a, pred_b and actual columns, found {} columns",
+                        row.len()
+                    );
+                    std::process::exit(1);
+                }
+
+                data.push(row);
+            }
+
+            warn_missing(rows.missing_counts(), missing);
+
+            if data.is_empty() {
+                eprintln!("input must be a non empty set");
+                std::process::exit(1);
+            }
+
+            // predictions with a fraction are scores of a binary prediction, otherwise labels
+            let scores: Option<Vec<(f64, f64)>> = data
+                .iter()
+                .map(|row| Some((row[0].parse().ok()?, row[1].parse().ok()?)))
+                .collect();
+            let scores =
+                scores.filter(|s| s.iter().any(|(a, b)| a.fract() != 0.0 || b.fract() != 0.0));
+
+            let mut labels = series::Labels::default();
+            let actual: Vec<usize> = data.iter().map(|row| labels.push(&row[2])).collect();
+
+            // (a right, b right, score a, score b, actual is the second class) of each row
+            let rows: Vec<(bool, bool, f64, f64, bool)> = match &scores {
+                Some(scores) => {
+                    if labels.len() != 2 {
+                        eprintln!(
+                            "scores need two classes in the actual column, found {}",
+                            labels.len()
+                        );
+                        std::process::exit(1);
+                    }
+
+                    let order = labels.sort();
+                    scores
+                        .iter()
+                        .zip(&actual)
+                        .map(|((a, b), y)| {
+                            let positive = order[*y] == 1;
+                            (
+                                (*a >= threshold) == positive,
+                                (*b >= threshold) == positive,
+                                *a,
+                                *b,
+                                positive,
+                            )
+                        })
+                        .collect()
+                }
+                None => data
+                    .iter()
+                    .zip(&actual)
+                    .map(|(row, y)| {
+                        (
+                            labels.get(&row[0]) == Some(*y),
+                            labels.get(&row[1]) == Some(*y),
+                            f64::NAN,
+                            f64::NAN,
+                            false,
+                        )
+                    })
+                    .collect(),
+            };
+
+            // the accuracy of both models and, for scores, their AUC
+            let metrics = |indices: &mut dyn Iterator<Item = usize>| -> Vec<f64> {
+                let (mut right_a, mut right_b, mut n) = (0, 0, 0);
+                let (mut auc_a, mut auc_b) = (vec![], vec![]);
+
+                for i in indices {
+                    let (a, b, score_a, score_b, positive) = rows[i];
+                    right_a += a as usize;
+                    right_b += b as usize;
+                    n += 1;
+
+                    auc_a.push((score_a, positive));
+                    auc_b.push((score_b, positive));
+                }
+
+                let mut out = vec![right_a as f64 / n as f64, right_b as f64 / n as f64];
+                if scores.is_some() {
+                    out.push(series::roc(&auc_a).auc);
+                    out.push(series::roc(&auc_b).auc);
+                }
+
+                out
+            };
+
+            let estimates = metrics(&mut (0..rows.len()));
+            let names = ["accuracy", "auc"];
+
+            let mut models = Section::new("models", &["model", "metric", "value"]);
+            for (i, value) in estimates.iter().enumerate() {
+                models.push(vec![
+                    Value::from(["a", "b"][i % 2]),
+                    Value::from(names[i / 2]),
+                    Value::from(*value),
+                ]);
+            }
+
+            let test = series::mcnemar(&rows.iter().map(|r| (r.0, r.1)).collect::<Vec<_>>());
+            let mut mcnemar = Section::new(
+                "mcnemar",
+                &["a_only", "b_only", "statistic", "p_value", "exact_p_value"],
+            );
+            mcnemar.push(vec![
+                Value::from(test.b),
+                Value::from(test.c),
+                Value::from(test.statistic),
+                Value::from(test.p_value),
+                Value::from(test.exact_p_value),
+            ]);
+
+            let mut sections = vec![models, mcnemar];
+
+            if scores.is_some() {
+                let test =
+                    series::delong(&rows.iter().map(|r| (r.2, r.3, r.4)).collect::<Vec<_>>());
+                let mut delong = Section::new(
+                    "delong",
+                    &["auc_a", "auc_b", "difference", "se", "z", "p_value"],
+                );
+                delong.push(vec![
+                    Value::from(test.auc_a),
+                    Value::from(test.auc_b),
+                    Value::from(test.difference),
+                    Value::from(test.se),
+                    Value::from(test.z),
+                    Value::from(test.p_value),
+                ]);
+
+                sections.push(delong);
+            }
+
+            // the same resample of rows is scored for both models, so the differences are paired
+            if bootstrap > 0 {
+                let mut sampler = bootstrap::Bootstrap::new(rows.len(), seed);
+                let mut indices = vec![];
+                let mut differences = vec![vec![]; estimates.len() / 2];
+
+                for _ in 0..bootstrap {
+                    sampler.sample(&mut indices);
+                    let m = metrics(&mut indices.iter().copied());
+
+                    for (i, d) in differences.iter_mut().enumerate() {
+                        d.push(m[2 * i] - m[2 * i + 1]);
+                    }
+                }
+
+                let mut paired =
+                    Section::new("bootstrap", &["metric", "difference", "lower", "upper"]);
+
+                for (i, d) in differences.iter().enumerate() {
+                    let (lower, upper) = bootstrap::interval(d, ci);
+
+                    paired.push(vec![
+                        Value::from(names[i]),
+                        Value::from(estimates[2 * i] - estimates[2 * i + 1]),
+                        Value::from(lower),
+                        Value::from(upper),
+                    ]);
+                }
+
+                sections.push(paired);
+            }
+
+            print!("{}", report::render(format, &sections));
+        }
+
+        Command::Eval {
+            mode:
+                Some(EvalOptions::Regression {
+                    column,
+                    probabilities,
+                    input,
+                }),
+            ..
+        } => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            let probabilities = parse_probabilities(Some(probabilities), 0);
+
+            let (tuples, counts) = or_exit(series::to_tuple(
+                get_reader(input),
+                with_header,
+                column.as_ref(),
+                delimiter,
+                missing,
+            ));
+            warn_missing(&counts, missing);
+
+            let tuples: Vec<(f64, f64)> = tuples
+                .into_iter()
+                .map(|(p, a)| (p as f64, a as f64))
+                .collect();
+
+            if tuples.is_empty() {
+                eprintln!("input must be a non empty set");
+                std::process::exit(1);
+            }
+
+            let r = series::regression(&tuples, &probabilities);
+
+            let mut metrics = Section::new(
+                "regression",
+                &[
+                    "n",
+                    "mae",
+                    "mse",
+                    "rmse",
+                    "mape",
+                    "r2",
+                    "explained_variance",
+                    "median_absolute_error",
+                ],
+            );
+            metrics.push(vec![
+                Value::from(r.n),
+                Value::from(r.mae),
+                Value::from(r.mse),
+                Value::from(r.rmse),
+                Value::from(r.mape),
+                Value::from(r.r2),
+                Value::from(r.explained_variance),
+                Value::from(r.median_absolute_error),
+            ]);
+
+            let mut residuals = Section::new("residuals", &["quantile", "residual"]);
+            for (p, v) in &r.residuals {
+                residuals.push(vec![Value::from(*p), Value::from(*v)]);
+            }
+
+            print!("{}", report::render(format, &[metrics, residuals]));
+        }
+
+        Command::Xgb(XgbOptions::Train {
+            ycol,
+            params,
+            model_out: output,
+            objective,
+            group,
+            nclasses,
+            valid,
+            valid_frac,
+            eval_log,
+            column,
+            input,
+        }) => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Nan);
+            let params = params.params();
+
+            if valid.is_some() && valid_frac.is_some() {
+                eprintln!("--valid and --valid-frac can't be used together");
+                std::process::exit(1);
+            }
+
+            if let Some(frac) = valid_frac {
+                if !(frac > 0.0 && frac < 1.0) {
+                    eprintln!("--valid-frac must be between 0 and 1");
+                    std::process::exit(1);
+                }
+            }
+
+            if params.early_stopping_rounds.is_some() && valid.is_none() && valid_frac.is_none() {
+                eprintln!(
+                    "early stopping needs a validation set, given with --valid or --valid-frac"
+                );
+                std::process::exit(1);
+            }
+
+            let ranking = ranking(&objective, group);
+
+            let read = |input: Option<PathBuf>| {
+                let matrix = or_exit(series::to_grouped_matrix(
+                    get_reader(input),
+                    ycol,
+                    group,
+                    with_header,
+                    column.as_ref(),
+                    delimiter,
+                    missing,
+                ));
+                warn_missing(&matrix.missing, missing);
+                matrix
+            };
+
+            let series::Matrix {
+                xdata,
+                mut ydata,
+                labels,
+                groups,
+                ..
+            } = read(input);
+
+            let (labels, nclasses) = encode_labels(&objective, &mut ydata, labels, nclasses);
+
+            // the validation set, with its labels encoded as those of the input
+            let (train, valid) = match (valid, valid_frac) {
+                (Some(path), _) => {
+                    let matrix = read(Some(path));
+
+                    let valid_y = match &labels {
+                        Some(labels) => matrix
+                            .ydata
+                            .iter()
+                            .map(|y| {
+                                let name = match &matrix.labels {
+                                    Some(names) => names.name(*y as usize).unwrap().to_string(),
+                                    None => y.to_string(),
+                                };
+
+                                match labels.get(&name) {
+                                    Some(i) => i as f32,
+                                    None => {
+                                        eprintln!("validation label {} is not in the input", name);
+                                        std::process::exit(1);
+                                    }
+                                }
+                            })
+                            .collect(),
+                        None if matrix.labels.is_some() => {
+                            eprintln!("{} needs numeric labels", objective);
+                            std::process::exit(1);
+                        }
+                        None => matrix.ydata,
+                    };
+
+                    (
+                        (xdata, ydata, groups),
+                        Some((matrix.xdata, valid_y, matrix.groups)),
+                    )
+                }
+                (None, Some(frac)) => {
+                    let (train, valid) = holdout(&xdata, &ydata, &groups, frac, params.seed);
+                    (train, Some(valid))
+                }
+                (None, None) => ((xdata, ydata, groups), None),
+            };
+
+            let dataset = |(x, y, g): (Vec<Vec<f64>>, Vec<f32>, Vec<u32>)| {
+                let mut dmat = or_exit(xgb::to_xgboost_dataset(&x, Some(y)));
+                if ranking {
+                    or_exit(xgb::set_groups(&mut dmat, &g));
+                }
+                dmat
+            };
+
+            let training_set = dataset(train);
+            let valid_set = valid.map(dataset);
+
+            let history = or_exit(xgb::train(
+                &training_set,
+                valid_set.as_ref(),
+                &objective,
+                nclasses,
+                &params,
+                &output,
+                labels.map(|l| l.to_string()).as_deref(),
+            ));
 
-    out
-}
+            if let Some(path) = eval_log {
+                write_eval_log(&path, &history);
+            }
+        }
 
-fn print_quintiles(input: &mut [f64], k: u32) {
-    if input.len() < (k as usize) {
-        eprintln!(
-            "insufficient data for {} quintiles, data has only {} rows",
+        Command::Xgb(XgbOptions::Cv {
+            ycol,
             k,
-            input.len()
-        );
-        return;
-    }
+            stratify,
+            params,
+            objective,
+            group,
+            nclasses,
+            oof,
+            column,
+            input,
+        }) => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Nan);
+            let params = params.params();
+            let ranking = ranking(&objective, group);
 
-    input.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            if k < 2 {
+                eprintln!("-k must be at least 2");
+                std::process::exit(1);
+            }
 
-    let mut ks = vec![];
-    let size = input.len() / (k as usize);
+            if params.rounds == 0 {
+                eprintln!("-r must be at least 1");
+                std::process::exit(1);
+            }
 
-    for i in 1..k {
-        ks.push(input[(i as usize) * size] as f32);
-    }
+            if stratify && group.is_some() {
+                eprintln!("--stratify can't be used with groups, folds are split by group");
+                std::process::exit(1);
+            }
 
-    for i in 0..k - 1 {
-        let perc = (((i + 1) * size as u32) as f32) / input.len() as f32;
-        let perc_format = format!("{}%", (perc * 100.0) as u32);
-        println!("{:<8} {:<8}", perc_format, ks[i as usize]);
-    }
-}
+            let matrix = or_exit(series::to_grouped_matrix(
+                get_reader(input),
+                ycol,
+                group,
+                with_header,
+                column.as_ref(),
+                delimiter,
+                missing,
+            ));
+            warn_missing(&matrix.missing, missing);
 
-/// calculates and normalizes the byte histogram
-fn to_byte_histogram(bytes: &[u8]) -> Vec<f64> {
-    let mut histo: Vec<f64> = Vec::with_capacity(256);
+            let series::Matrix {
+                xdata,
+                mut ydata,
+                labels,
+                groups,
+                ..
+            } = matrix;
 
-    for _ in 0..256 {
-        histo.push(0.0)
-    }
+            let (labels, nclasses) = encode_labels(&objective, &mut ydata, labels, nclasses);
 
-    bytes.iter().for_each(|b| {
-        histo[*b as usize] += 1.0;
-    });
+            if stratify && labels.is_none() {
+                eprintln!("--stratify needs a binary or multi objective");
+                std::process::exit(1);
+            }
 
-    let sum = bytes.len() as f64;
+            let sizes = units(xdata.len(), &groups);
+            if sizes.len() < k {
+                let unit = if ranking { "groups" } else { "rows" };
+                eprintln!("-k {} is more than the {} {}", k, sizes.len(), unit);
+                std::process::exit(1);
+            }
 
-    histo.iter().map(|x| x / sum).collect()
-}
+            let fold_of = assign_folds(&ydata, &groups, k, stratify, params.seed);
 
-fn main() {
-    let opt = Opt::from_args();
+            // the rows each fold holds out, in order
+            let mut held_rows = vec![vec![]; k];
+            let mut row = 0;
+            for (unit, size) in sizes.iter().enumerate() {
+                held_rows[fold_of[unit]].extend(row..row + *size as usize);
+                row += *size as usize;
+            }
 
-    match opt.cmd {
-        Command::Summary {
-            transpose,
-            with_header,
-            input,
-        } => {
-            let raw_inputs = get_input(input);
-            let data = series::to_vector(&raw_inputs, with_header);
-            let series = series::Series::new(data);
+            let dataset = |(x, y, g): Rows| {
+                let mut dmat = or_exit(xgb::to_xgboost_dataset(&x, Some(y)));
+                if ranking {
+                    or_exit(xgb::set_groups(&mut dmat, &g));
+                }
+                dmat
+            };
 
-            if transpose {
-                series.summary_t();
-            } else {
-                series.summary();
+            let folds: Vec<_> = (0..k)
+                .map(|fold| {
+                    let held: Vec<bool> = fold_of.iter().map(|f| *f == fold).collect();
+                    let (train, valid) = split(&xdata, &ydata, &groups, &held);
+                    (dataset(train), dataset(valid))
+                })
+                .collect();
+
+            let result = or_exit(xgb::cv(&folds, &objective, nclasses, &params));
+
+            let mut rounds = Section::new(
+                "rounds",
+                &[
+                    "round",
+                    "metric",
+                    "train_mean",
+                    "train_std",
+                    "valid_mean",
+                    "valid_std",
+                ],
+            );
+
+            for (round, (train, valid)) in result.train.iter().zip(&result.valid).enumerate() {
+                let (train_mean, train_std) = mean_std(train);
+                let (valid_mean, valid_std) = mean_std(valid);
+
+                rounds.push(vec![
+                    Value::from(round),
+                    Value::from(result.metric.as_str()),
+                    Value::from(train_mean),
+                    Value::from(train_std),
+                    Value::from(valid_mean),
+                    Value::from(valid_std),
+                ]);
             }
-        }
 
-        Command::Quintiles {
-            quintiles,
-            with_header,
-            input,
-        } => {
-            let raw_inputs = get_input(input);
-            let mut data = series::to_vector(&raw_inputs, with_header);
-            print_quintiles(&mut data, quintiles);
-        }
+            let mut best = Section {
+                name: "best",
+                columns: rounds.columns.clone(),
+                rows: vec![],
+            };
+            best.push(rounds.rows[result.best_iteration].clone());
+
+            print!("{}", report::render(format, &[rounds, best]));
+
+            if let Some(path) = oof {
+                // a multi:softmax class is written as its label, like xgb predict
+                let classes = labels.as_ref().filter(|_| objective == "multi:softmax");
+                let name = |labels: Option<&series::Labels>, y: f32| match labels
+                    .and_then(|l| l.name(y as usize))
+                {
+                    Some(name) => name.to_string(),
+                    None => y.to_string(),
+                };
 
-        Command::Eval {
-            threshold,
-            verbose,
-            bayes,
-            input,
-        } => {
-            let raw_inputs = get_input(input);
-            let tuples = series::to_tuple(&raw_inputs);
+                let mut lines = vec![String::new(); xdata.len()];
+                for (rows, predictions) in held_rows.iter().zip(&result.predictions) {
+                    let width = predictions.len() / rows.len().max(1);
 
-            let bases: Vec<f32> = if let Some(s) = bayes {
-                match series::str_to_vector(&s, ",") {
-                    Ok(xs) => xs,
-                    Err(_) => {
-                        eprintln!("error parsing -b list");
-                        std::process::exit(1);
+                    for (i, row) in rows.iter().enumerate() {
+                        let mut cells: Vec<String> = predictions[i * width..(i + 1) * width]
+                            .iter()
+                            .map(|p| name(classes, *p))
+                            .collect();
+                        cells.push(name(labels.as_ref(), ydata[*row]));
+
+                        lines[*row] = cells.join(",");
                     }
                 }
-            } else {
-                vec![]
-            };
 
-            let matrix = series::confusion_matrix(&tuples, threshold);
-            let stats = series::confusion_matrix_stats(&matrix);
+                let mut out = lines.join("\n");
+                out.push('\n');
 
-            let size = matrix.len();
+                if let Err(e) = std::fs::write(&path, out) {
+                    eprintln!("failed to write {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
 
-            // convert the matrix into a formatted string for stdout
-            let mut header = String::new();
-            let mut body = String::new();
-            header.push_str("Confusion Matrix\n");
-            header.push_str("Predicted on y-axis, Actual on x-axis\n");
-            header.push('\n');
-            header.push_str(&format!("{:<8}", "-"));
+        Command::Xgb(XgbOptions::Tune {
+            ycol,
+            space,
+            search,
+            trials,
+            k,
+            valid_frac,
+            stratify,
+            jobs,
+            params,
+            model_out: output,
+            params_out,
+            objective,
+            group,
+            nclasses,
+            column,
+            input,
+        }) => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Nan);
+            use rand::SeedableRng;
 
-            for i in 0..size {
-                header.push_str(&format!("{:<8}", size - 1 - i));
+            let base = params.params();
+            let ranking = ranking(&objective, group);
 
-                body.push_str(&format!("{:<8}", size - 1 - i));
+            if k.map(|k| k < 2).unwrap_or(false) {
+                eprintln!("-k must be at least 2");
+                std::process::exit(1);
+            }
 
-                for j in 0..size {
-                    body.push_str(&format!("{:<8}", matrix[i][j]));
+            if let Some(frac) = valid_frac {
+                if !(frac > 0.0 && frac < 1.0) {
+                    eprintln!("--valid-frac must be between 0 and 1");
+                    std::process::exit(1);
                 }
 
-                body.push('\n');
+                if stratify {
+                    eprintln!("--stratify is only used with folds");
+                    std::process::exit(1);
+                }
             }
 
-            // print matrix to stdout
-            println!("{}", header);
-            println!("{}", body);
-
-            let mut bayes_calc_str = String::new();
-            let mut verbose_str = String::new();
+            if stratify && group.is_some() {
+                eprintln!("--stratify can't be used with groups, folds are split by group");
+                std::process::exit(1);
+            }
 
-            verbose_str.push_str(&format!(
-                "{:<8}{:<8}{:<8}{:<8}{:<8}\n",
-                "class", "tpr", "fpr", "tnr", "fnr"
-            ));
+            let trials = match (search, trials) {
+                (tune::Search::Grid, Some(_)) => {
+                    eprintln!(
+                        "--trials only applies to --search random, grid tries every combination"
+                    );
+                    std::process::exit(1);
+                }
+                (_, Some(0)) => {
+                    eprintln!("--trials must be at least 1");
+                    std::process::exit(1);
+                }
+                (_, trials) => trials.unwrap_or(20),
+            };
 
-            for stat in stats {
-                if verbose > 0 {
-                    verbose_str.push_str(&format!(
-                        "{:<8}{:<8.3}{:<8.3}{:<8.3}{:<8.3}\n",
-                        stat.label, stat.tpr, stat.fpr, stat.tnr, stat.fnr
-                    ));
+            let path = space;
+            let space: tune::Space = match std::fs::read_to_string(&path) {
+                Ok(text) => match text.parse() {
+                    Ok(space) => space,
+                    Err(e) => {
+                        eprintln!("{}: {}", path.display(), e);
+                        std::process::exit(1);
+                    }
+                },
+                Err(e) => {
+                    eprintln!("failed to read search space {}: {}", path.display(), e);
+                    std::process::exit(1);
                 }
+            };
 
-                if !bases.is_empty() {
-                    if bases.len() != size {
-                        eprintln!(
-                            "invalid number of baseline values, it must match the number of classes"
-                        );
+            // the trials are drawn up front, so they only depend on the seed
+            let mut rng = rand::rngs::StdRng::seed_from_u64(base.seed);
+            let choices = match search {
+                tune::Search::Grid => match space.grid() {
+                    Ok(choices) => choices,
+                    Err(e) => {
+                        eprintln!("{}: {}", path.display(), e);
                         std::process::exit(1);
                     }
+                },
+                tune::Search::Random => space.random(trials, &mut rng),
+            };
 
-                    let prob_positive =
-                        (stat.tpr * bases[stat.label]) + (stat.fpr * (1.0 - bases[stat.label]));
+            let jobs = jobs
+                .unwrap_or_else(|| {
+                    std::thread::available_parallelism()
+                        .map(|n| n.get())
+                        .unwrap_or(1)
+                })
+                .clamp(1, choices.len());
+
+            let candidates: Vec<xgb::Params> = choices
+                .iter()
+                .map(|choice| {
+                    let mut params = base.clone();
+                    for (key, value) in choice {
+                        if let Err(e) = set_param(&mut params, key, value) {
+                            eprintln!("{}: {}", path.display(), e);
+                            std::process::exit(1);
+                        }
+                    }
 
-                    let prob_class_given_positive = (stat.tpr * bases[stat.label]) / prob_positive;
+                    if params.rounds == 0 {
+                        eprintln!("num_round must be at least 1");
+                        std::process::exit(1);
+                    }
 
-                    bayes_calc_str.push_str(&format!(
-                        "{}: Pr(class_{}|positive) = {}\n",
-                        stat.label, stat.label, prob_class_given_positive
-                    ));
-                }
-            }
+                    // the trials share the cores rather than each taking them all
+                    if jobs > 1 {
+                        params.threads = Some(1);
+                    }
 
-            if verbose > 0 {
-                print!("{}", verbose_str);
-                println!();
-            }
+                    params
+                })
+                .collect();
+
+            let matrix = or_exit(series::to_grouped_matrix(
+                get_reader(input),
+                ycol,
+                group,
+                with_header,
+                column.as_ref(),
+                delimiter,
+                missing,
+            ));
+            warn_missing(&matrix.missing, missing);
 
-            if !bases.is_empty() {
-                println!("Bayes estimates with baseline rates\n");
-                print!("{}", bayes_calc_str);
-                println!();
-            }
+            let series::Matrix {
+                xdata,
+                mut ydata,
+                labels,
+                groups,
+                ..
+            } = matrix;
 
-            if verbose > 1 && matrix.len() == 2 {
-                let output = series::threshold_table_stats(&tuples);
+            let (labels, nclasses) = encode_labels(&objective, &mut ydata, labels, nclasses);
 
-                println!("ROC table\n");
-                println!("{:<8}{:<8}{:<8}{:<8}{:<8}", "t", "prec", "f1", "tpr", "fpr");
+            if stratify && labels.is_none() {
+                eprintln!("--stratify needs a binary or multi objective");
+                std::process::exit(1);
+            }
 
-                for row in output {
-                    println!(
-                        "{:<8.2}{:<8.4}{:<8.4}{:<8.4}{:<8.4}",
-                        row[0], row[1], row[2], row[3], row[4]
-                    );
+            // the (training, validation) rows every trial is scored on
+            let folds: Vec<(Rows, Rows)> = match valid_frac {
+                Some(frac) => vec![holdout(&xdata, &ydata, &groups, frac, base.seed)],
+                None => {
+                    let k = k.unwrap_or(5);
+                    let units = units(xdata.len(), &groups).len();
+                    if units < k {
+                        let unit = if ranking { "groups" } else { "rows" };
+                        eprintln!("-k {} is more than the {} {}", k, units, unit);
+                        std::process::exit(1);
+                    }
+
+                    let fold_of = assign_folds(&ydata, &groups, k, stratify, base.seed);
+                    (0..k)
+                        .map(|fold| {
+                            let held: Vec<bool> = fold_of.iter().map(|f| *f == fold).collect();
+                            split(&xdata, &ydata, &groups, &held)
+                        })
+                        .collect()
                 }
-            }
-        }
+            };
 
-        Command::Xgb(XgbOptions::Train {
-            ycol,
-            depth,
-            eta,
-            model_out: output,
-            objective,
-            nclasses,
-            rounds,
-            with_header,
-            input,
-        }) => {
-            let raw_inputs = get_input(input);
-            let (xdata, ydata) = series::to_matrix(&raw_inputs, ycol, with_header);
+            let dataset = |(x, y, g): &Rows| {
+                let mut dmat = xgb::to_xgboost_dataset(x, Some(y.clone()))?;
+                if ranking {
+                    xgb::set_groups(&mut dmat, g)?;
+                }
+                Ok(dmat)
+            };
 
-            let training_set = xgb::to_xgboost_dataset(&xdata, Some(ydata));
+            let results = or_exit(tune::run(
+                &folds,
+                dataset,
+                &candidates,
+                &objective,
+                nclasses,
+                jobs,
+            ));
+            let trials: Vec<tune::Trial> = results
+                .iter()
+                .map(|result| tune::Trial::new(result, base.early_stopping_rounds.is_some()))
+                .collect();
+            let order = tune::rank(&trials);
+
+            let leaderboard = tune::leaderboard(&space, &choices, &trials, &order);
+            print!("{}", report::render(format, &[leaderboard]));
+
+            // the best parameters are trained on the whole input for the rounds they were
+            // scored at
+            let mut best = candidates[order[0]].clone();
+            best.rounds = trials[order[0]].rounds as u32;
+            best.early_stopping_rounds = None;
+            best.threads = None;
+
+            let params_out =
+                params_out.unwrap_or_else(|| PathBuf::from(format!("{}.json", output)));
+            let json = serde_json::to_string_pretty(&params_json(&best)).unwrap();
+            if let Err(e) = std::fs::write(&params_out, json + "\n") {
+                eprintln!("failed to write {}: {}", params_out.display(), e);
+                std::process::exit(1);
+            }
 
-            xgb::train(
-                &training_set,
+            or_exit(xgb::train(
+                &or_exit(dataset(&(xdata, ydata, groups))),
+                None,
                 &objective,
                 nclasses,
-                depth,
-                eta,
-                rounds,
+                &best,
                 &output,
-            );
+                labels.map(|l| l.to_string()).as_deref(),
+            ));
         }
 
         Command::Xgb(XgbOptions::Predict {
             ycol,
             model_in,
-            with_header,
+            calibrator,
+            column,
             input,
         }) => {
-            let inputs = get_input(input);
-            let (xdata, ydata) = series::to_matrix(&inputs, ycol, with_header);
-            let test_set = xgb::to_xgboost_dataset(&xdata, None);
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Nan);
+            let reader = get_reader(input);
+            let matrix = or_exit(series::to_matrix(
+                reader,
+                ycol,
+                with_header,
+                column.as_ref(),
+                delimiter,
+                missing,
+            ));
+            warn_missing(&matrix.missing, missing);
+
+            let series::Matrix {
+                xdata,
+                ydata,
+                labels,
+                ..
+            } = matrix;
+            let test_set = or_exit(xgb::to_xgboost_dataset(&xdata, None));
 
-            let predicted = xgb::predict(&model_in, &test_set);
+            let mut predicted = or_exit(xgb::predict(&model_in, &test_set));
             let mut buf = String::new();
 
+            // a multi:softmax class is printed as the label it was trained on
+            let classes: Option<series::Labels> =
+                match or_exit(xgb::attribute(&model_in, "objective")).as_deref() {
+                    Some("multi:softmax") => or_exit(xgb::attribute(&model_in, "labels"))
+                        .and_then(|labels| labels.parse().ok()),
+                    _ => None,
+                };
+
+            // multi:softprob predicts a probability for each class, printed in class order
+            let k = predicted.len() / xdata.len().max(1);
+
+            if let Some(path) = calibrator {
+                if k != 1 || classes.is_some() {
+                    eprintln!("--calibrator needs a model with a single score per row");
+                    std::process::exit(1);
+                }
+
+                let calibrator = read_calibrator(&path);
+                for p in predicted.iter_mut() {
+                    *p = calibrator.apply(*p as f64) as f32;
+                }
+            }
+
             for (index, row) in xdata.iter().enumerate() {
                 let mut xs = String::new();
                 let size = row.len();
@@ -513,10 +3727,26 @@ fn main() {
                     }
                 }
 
+                let p: Vec<String> = predicted[index * k..(index + 1) * k]
+                    .iter()
+                    .map(
+                        |p| match classes.as_ref().and_then(|c| c.name(*p as usize)) {
+                            Some(name) => name.to_string(),
+                            None => p.to_string(),
+                        },
+                    )
+                    .collect();
+                let p = p.join(",");
+
                 if ydata.is_empty() {
-                    buf.push_str(&format!("{},{}\n", predicted[index], xs));
+                    buf.push_str(&format!("{},{}\n", p, xs));
                 } else {
-                    buf.push_str(&format!("{},{},{}\n", predicted[index], ydata[index], xs));
+                    let y = match labels.as_ref().and_then(|l| l.name(ydata[index] as usize)) {
+                        Some(name) => name.to_string(),
+                        None => ydata[index].to_string(),
+                    };
+
+                    buf.push_str(&format!("{},{},{}\n", p, y, xs));
                 };
 
                 if index % 1000 == 0 {
@@ -551,32 +3781,94 @@ fn main() {
                 input
             };
 
-            xgb::dump_model(&bytes, dump_model, &typ);
+            let dump = or_exit(xgb::dump_model(&bytes));
+
+            if dump_model {
+                println!("{}", dump);
+                return;
+            }
+
+            let list = or_exit(xgb::importance(&dump, &typ));
+
+            if format == Format::Table {
+                for (name, val) in list {
+                    println!("{} = {}", name, val);
+                }
+                return;
+            }
+
+            let mut section = Section::new("importance", &["feature", "importance"]);
+            for (name, val) in list {
+                section.push(vec![Value::from(name), report::from_f32(val)]);
+            }
+
+            print!("{}", report::render(format, &[section]));
         }
 
         Command::CorMatrix {
             ycol,
-            with_header,
+            column,
             input,
         } => {
-            let input = get_input(input);
-            let (xdata, _) = series::to_matrix(&input, ycol, with_header);
-            let matrix = series::correlation_matrix(&xdata);
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            // the correlation is accumulated a row at a time
+            let mut rows = or_exit(series::Rows::new(
+                get_reader(input),
+                delimiter,
+                with_header,
+                column,
+            ))
+            .without(ycol)
+            .missing(missing);
+            let mut cor: Option<series::OnlineCorrelation> = None;
+
+            for row in series::complete_rows(&mut rows) {
+                let row = or_exit(row);
+                let cor = cor.get_or_insert_with(|| series::OnlineCorrelation::new(row.len()));
+                or_exit(cor.push(&row));
+            }
 
-            let size = matrix.len();
+            warn_missing(rows.missing_counts(), missing);
+
+            let matrix = match cor {
+                Some(cor) => cor.matrix(),
+                None => {
+                    eprintln!("input must be a non empty set");
+                    std::process::exit(1);
+                }
+            };
+
+            let header = rows.names();
+
+            if format != Format::Table {
+                let mut columns = vec!["column"];
+                columns.extend(header.iter().map(|h| h.as_str()));
 
-            print!("{:<8}", "-");
-            for i in 0..size {
-                print!("{:<8}", i);
+                // the full symmetric matrix, the table only shows the lower triangle
+                let mut section = Section::new("correlation", &columns);
+                for (i, name) in header.iter().enumerate() {
+                    let mut row = vec![Value::from(name.as_str())];
+                    row.extend((0..header.len()).map(|j| Value::from(matrix[i.max(j)][i.min(j)])));
+                    section.push(row);
+                }
+
+                print!("{}", report::render(format, &[section]));
+                return;
+            }
+
+            // columns are as wide as the longest column name
+            let width = header.iter().map(|h| h.len() + 1).max().unwrap_or(0).max(8);
+
+            print!("{:<w$}", "-", w = width);
+            for name in &header {
+                print!("{:<w$}", name, w = width);
             }
             println!();
 
-            for i in 0..size {
-                print!("{:<8}", i);
-                for j in 0..size {
-                    if j < i + 1 {
-                        print!("{:<8.2}", matrix[i][j]);
-                    }
+            for (i, row) in matrix.iter().enumerate() {
+                print!("{:<w$}", header[i], w = width);
+                for value in row.iter().take(i + 1) {
+                    print!("{:<w$.2}", value, w = width);
                 }
                 println!();
             }
@@ -634,5 +3926,165 @@ fn main() {
             }
             println!("{}", out_str);
         }
+
+        Command::Graph(GraphOptions::Hist { bins, width, input }) => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            if bins == 0 || width == 0 {
+                eprintln!("the bins and width of a histogram must be at least 1");
+                std::process::exit(1);
+            }
+
+            let reader = get_reader(input);
+            let (data, counts) = or_exit(series::to_vector(
+                reader,
+                with_header,
+                None,
+                delimiter,
+                missing,
+            ));
+            warn_missing(&counts, missing);
+
+            if data.is_empty() {
+                eprintln!("input must be a non empty set");
+                std::process::exit(1);
+            }
+
+            print!("{}", graph::histogram(&data, bins, width));
+        }
+
+        Command::Graph(GraphOptions::Line {
+            width,
+            height,
+            input,
+        }) => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            check_canvas(width, height);
+
+            let reader = get_reader(input);
+            let points = to_points(reader, with_header, delimiter, missing);
+            print!("{}", graph::line(&points, width, height));
+        }
+
+        Command::Graph(GraphOptions::Scatter {
+            width,
+            height,
+            input,
+        }) => {
+            let (with_header, delimiter, missing, input) = input.parts(series::Missing::Error);
+            check_canvas(width, height);
+
+            let reader = get_reader(input);
+            let points = to_points(reader, with_header, delimiter, missing);
+            print!("{}", graph::scatter(&points, width, height));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn rows(n: usize) -> (Vec<Vec<f64>>, Vec<f32>) {
+        let xdata = (0..n).map(|i| vec![i as f64]).collect();
+        let ydata = (0..n).map(|i| (i % 3) as f32).collect();
+        (xdata, ydata)
+    }
+
+    #[test]
+    fn test_percentile_label() {
+        let labels: Vec<String> = [7.0, 12.5, 0.1, 99.9, 50.0, 0.0, 100.0]
+            .iter()
+            .map(|p| percentile_label(p / 100.0))
+            .collect();
+        assert_eq!(
+            labels,
+            ["p7", "p12.5", "p0.1", "p99.9", "p50", "p0", "p100"]
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let (xdata, ydata) = rows(6);
+        let groups = vec![2, 1, 3];
+
+        let (kept, out) = split(&xdata, &ydata, &groups, &[false, true, false]);
+
+        assert_eq!(
+            kept.0,
+            vec![vec![0.0], vec![1.0], vec![3.0], vec![4.0], vec![5.0]]
+        );
+        assert_eq!(kept.1, vec![0.0, 1.0, 0.0, 1.0, 2.0]);
+        assert_eq!(kept.2, vec![2, 3]);
+        assert_eq!(out.0, vec![vec![2.0]]);
+        assert_eq!(out.2, vec![1]);
+
+        let (kept, out) = split(
+            &xdata,
+            &ydata,
+            &[],
+            &[true, false, false, false, false, true],
+        );
+        assert_eq!(kept.0.len() + out.0.len(), 6);
+        assert_eq!(out.1, vec![0.0, 2.0]);
+        assert!(kept.2.is_empty() && out.2.is_empty());
+    }
+
+    #[test]
+    fn test_holdout() {
+        let (xdata, ydata) = rows(10);
+
+        for frac in [0.0, 0.01, 0.5, 0.99, 1.0] {
+            let (train, valid) = holdout(&xdata, &ydata, &[], frac, 7);
+            assert!(!train.0.is_empty() && !valid.0.is_empty());
+            assert_eq!(train.0.len() + valid.0.len(), 10);
+        }
+
+        let (train, valid) = holdout(&xdata, &ydata, &[], 0.3, 7);
+        assert_eq!(valid.0.len(), 3);
+        assert_eq!(holdout(&xdata, &ydata, &[], 0.3, 7), (train, valid));
+
+        // groups are held out whole
+        let groups = vec![4, 1, 2, 3];
+        for seed in 0..10 {
+            let (train, valid) = holdout(&xdata, &ydata, &groups, 0.5, seed);
+            assert_eq!((train.2.len(), valid.2.len()), (2, 2));
+            assert_eq!(train.2.iter().sum::<u32>() as usize, train.0.len());
+            assert_eq!(valid.2.iter().sum::<u32>() as usize, valid.0.len());
+        }
+    }
+
+    #[test]
+    fn test_assign_folds() {
+        let (_, ydata) = rows(30);
+
+        for seed in 0..5 {
+            let folds = assign_folds(&ydata, &[], 4, false, seed);
+            assert_eq!(folds.len(), 30);
+            assert!(folds.iter().all(|fold| *fold < 4));
+
+            let mut sizes = vec![0; 4];
+            for fold in &folds {
+                sizes[*fold] += 1;
+            }
+            assert_eq!(sizes, vec![8, 8, 7, 7]);
+        }
+
+        // each fold gets its share of each class, 10 of each split 5 ways
+        let folds = assign_folds(&ydata, &[], 5, true, 3);
+        for class in 0..3 {
+            for fold in 0..5 {
+                let count = (0..30)
+                    .filter(|i| ydata[*i] == class as f32 && folds[*i] == fold)
+                    .count();
+                assert_eq!(count, 2);
+            }
+        }
+
+        // one fold per group, so a group is never split
+        let groups = vec![10, 5, 5, 10];
+        let folds = assign_folds(&ydata, &groups, 2, false, 1);
+        assert_eq!(folds.len(), 4);
+        assert_eq!(folds.iter().filter(|fold| **fold == 0).count(), 2);
     }
 }
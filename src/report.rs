@@ -0,0 +1,242 @@
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+/// Format is how a report is written to stdout. Table is the padded text meant to be read,
+/// the others have a stable schema meant to be parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Table,
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            _ => Err(format!(
+                "invalid format {}, expected table, csv, tsv, json or ndjson",
+                s
+            )),
+        }
+    }
+}
+
+/// Section is a single table of a report, e.g. the confusion matrix of eval. Each row holds
+/// one value per column.
+pub struct Section {
+    pub name: &'static str,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Section {
+    pub fn new(name: &'static str, columns: &[&str]) -> Self {
+        Section {
+            name,
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: vec![],
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+    }
+
+    fn object(&self, row: &[Value]) -> Map<String, Value> {
+        self.columns
+            .iter()
+            .cloned()
+            .zip(row.iter().cloned())
+            .collect()
+    }
+}
+
+/// cell formats a value for text output, floats are rounded and NaN is left empty
+fn cell(value: &Value, format: Format) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.to_string(),
+        Value::Number(n) if format == Format::Table && n.is_f64() => {
+            format!("{:.4}", n.as_f64().unwrap())
+        }
+        v => v.to_string(),
+    }
+}
+
+/// quote quotes a csv field holding the delimiter, a quote or a line break
+fn quote(field: String) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+fn delimited(section: &Section, format: Format) -> String {
+    let separator = if format == Format::Tsv { "\t" } else { "," };
+    let mut out = String::new();
+
+    let mut lines = vec![section.columns.clone()];
+    for row in &section.rows {
+        lines.push(row.iter().map(|v| cell(v, format)).collect());
+    }
+
+    for line in lines {
+        let fields: Vec<String> = if format == Format::Csv {
+            line.into_iter().map(quote).collect()
+        } else {
+            line
+        };
+
+        out.push_str(&fields.join(separator));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// table pads each column to its widest value, and at least 8 characters
+fn table(section: &Section) -> String {
+    let mut lines = vec![section.columns.clone()];
+    for row in &section.rows {
+        lines.push(row.iter().map(|v| cell(v, Format::Table)).collect());
+    }
+
+    let mut widths = vec![8; section.columns.len()];
+    for line in &lines {
+        for (w, field) in widths.iter_mut().zip(line) {
+            *w = (*w).max(field.chars().count() + 1);
+        }
+    }
+
+    let mut out = String::new();
+    for line in lines {
+        for (field, w) in line.iter().zip(&widths) {
+            out.push_str(&format!("{:<w$}", field, w = w));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// render writes the sections of a report. Text formats separate sections with a blank line,
+/// json is always an object of each section's rows by its name, and ndjson adds a "section"
+/// field to each row, so the schema is the same whatever the number of sections.
+pub fn render(format: Format, sections: &[Section]) -> String {
+    let mut out = String::new();
+
+    match format {
+        Format::Table | Format::Csv | Format::Tsv => {
+            for (index, section) in sections.iter().enumerate() {
+                if index > 0 {
+                    out.push('\n');
+                }
+
+                if format == Format::Table {
+                    out.push_str(&table(section));
+                } else {
+                    out.push_str(&delimited(section, format));
+                }
+            }
+        }
+
+        Format::Json => {
+            let array = |section: &Section| {
+                Value::Array(
+                    section
+                        .rows
+                        .iter()
+                        .map(|row| Value::Object(section.object(row)))
+                        .collect(),
+                )
+            };
+
+            let value = Value::Object(
+                sections
+                    .iter()
+                    .map(|s| (s.name.to_string(), array(s)))
+                    .collect(),
+            );
+
+            out.push_str(&value.to_string());
+            out.push('\n');
+        }
+
+        Format::Ndjson => {
+            for section in sections {
+                for row in &section.rows {
+                    let mut object = section.object(row);
+                    object.insert("section".to_string(), Value::from(section.name));
+
+                    out.push_str(&Value::Object(object).to_string());
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// from_f32 converts through the shortest decimal form of the f32, so 0.3f32 is written as 0.3
+/// rather than 0.30000001192092896
+pub fn from_f32(x: f32) -> Value {
+    Value::from(x.to_string().parse::<f64>().unwrap_or(f64::NAN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sections() -> Vec<Section> {
+        let mut scores = Section::new("scores", &["name", "score"]);
+        scores.push(vec![Value::from("a,\"b\""), from_f32(0.3)]);
+        scores.push(vec![Value::from("c"), from_f32(f32::NAN)]);
+
+        let mut counts = Section::new("counts", &["n"]);
+        counts.push(vec![Value::from(2)]);
+
+        vec![scores, counts]
+    }
+
+    #[test]
+    fn test_delimited() {
+        assert_eq!(
+            render(Format::Csv, &sections()),
+            "name,score\n\"a,\"\"b\"\"\",0.3\nc,\n\nn\n2\n"
+        );
+        assert_eq!(
+            render(Format::Tsv, &sections()),
+            "name\tscore\na,\"b\"\t0.3\nc\t\n\nn\n2\n"
+        );
+        assert_eq!(
+            render(Format::Table, &sections()[..1]),
+            "name    score   \na,\"b\"   0.3000  \nc               \n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        // a NaN is null, and a single section is still keyed by its name
+        assert_eq!(
+            render(Format::Json, &sections()[..1]),
+            "{\"scores\":[{\"name\":\"a,\\\"b\\\"\",\"score\":0.3},{\"name\":\"c\",\"score\":null}]}\n"
+        );
+        assert_eq!(
+            render(Format::Ndjson, &sections()),
+            "{\"name\":\"a,\\\"b\\\"\",\"score\":0.3,\"section\":\"scores\"}\n\
+             {\"name\":\"c\",\"score\":null,\"section\":\"scores\"}\n\
+             {\"n\":2,\"section\":\"counts\"}\n"
+        );
+    }
+}
@@ -0,0 +1,498 @@
+use crate::report::Section;
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde_json::{Map, Value};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Search is how the trials of xgb tune are chosen, every combination of the choices or a
+/// number of random draws
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Search {
+    Grid,
+    Random,
+}
+
+impl FromStr for Search {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grid" => Ok(Search::Grid),
+            "random" => Ok(Search::Random),
+            _ => Err(format!("invalid search {}, expected grid or random", s)),
+        }
+    }
+}
+
+/// Dimension is the values one parameter is searched over, a list of choices or a range. A
+/// range of whole numbers draws whole numbers, and a log range draws uniformly in log space,
+/// e.g. for eta.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dimension {
+    Choice(Vec<Value>),
+    Range {
+        min: f64,
+        max: f64,
+        log: bool,
+        whole: bool,
+    },
+}
+
+impl Dimension {
+    fn parse(key: &str, value: &Value) -> Result<Dimension, String> {
+        if let Value::Array(choices) = value {
+            if choices.is_empty() {
+                return Err(format!("{} has no choices", key));
+            }
+
+            return Ok(Dimension::Choice(choices.clone()));
+        }
+
+        let range = match value {
+            Value::Object(range) => range,
+            _ => {
+                return Err(format!(
+                    "{} must be a list of choices or a range, e.g. {{\"min\": 0.01, \"max\": 0.3}}",
+                    key
+                ))
+            }
+        };
+
+        for name in range.keys() {
+            if !["min", "max", "log"].contains(&name.as_str()) {
+                return Err(format!("{} has an unknown field {}", key, name));
+            }
+        }
+
+        let bound = |name: &str| match range.get(name) {
+            Some(Value::Number(n)) => Ok(n),
+            _ => Err(format!("{} needs a numeric {}", key, name)),
+        };
+        let (min, max) = (bound("min")?, bound("max")?);
+        let whole = min.is_u64() && max.is_u64();
+        let (min, max) = (min.as_f64().unwrap(), max.as_f64().unwrap());
+
+        let log = match range.get("log") {
+            Some(Value::Bool(log)) => *log,
+            None => false,
+            _ => return Err(format!("{} log must be true or false", key)),
+        };
+
+        if min > max || (log && min <= 0.0) {
+            return Err(format!(
+                "{} needs min <= max, and min > 0 for a log range",
+                key
+            ));
+        }
+
+        Ok(Dimension::Range {
+            min,
+            max,
+            log,
+            whole,
+        })
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> Value {
+        match self {
+            Dimension::Choice(choices) => choices[rng.gen_range(0..choices.len())].clone(),
+            Dimension::Range {
+                min,
+                max,
+                log,
+                whole,
+            } => {
+                let x = if *log {
+                    rng.gen_range(min.ln()..=max.ln()).exp()
+                } else {
+                    rng.gen_range(*min..=*max)
+                };
+
+                if *whole {
+                    Value::from((x.round() as u64).clamp(*min as u64, *max as u64))
+                } else if x == 0.0 {
+                    Value::from(0.0)
+                } else {
+                    // rounded to 4 significant digits, so the leaderboard stays readable
+                    let digits = 3 - x.abs().log10().floor() as i32;
+                    let scale = 10f64.powi(digits);
+                    Value::from((x * scale).round() / scale)
+                }
+            }
+        }
+    }
+}
+
+/// Space is the search space of xgb tune, read from a JSON object keyed by the XGBoost names
+/// of the parameters, e.g. {"max_depth": [3, 6, 9], "eta": {"min": 0.01, "max": 0.3, "log":
+/// true}}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Space {
+    pub dimensions: Vec<(String, Dimension)>,
+}
+
+impl FromStr for Space {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let object = match serde_json::from_str::<Value>(s) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => return Err("expected a json object".to_string()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        if object.is_empty() {
+            return Err("the search space has no parameters".to_string());
+        }
+
+        let dimensions = object
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), Dimension::parse(key, value)?)))
+            .collect::<Result<_, String>>()?;
+
+        Ok(Space { dimensions })
+    }
+}
+
+impl Space {
+    /// grid returns every combination of the choices, the last parameter varying fastest
+    pub fn grid(&self) -> Result<Vec<Map<String, Value>>, String> {
+        let mut trials = vec![Map::new()];
+
+        for (key, dimension) in &self.dimensions {
+            let choices = match dimension {
+                Dimension::Choice(choices) => choices,
+                Dimension::Range { .. } => {
+                    return Err(format!("grid search needs a list of choices for {}", key))
+                }
+            };
+
+            trials = trials
+                .into_iter()
+                .flat_map(|trial| {
+                    choices.iter().map(move |choice| {
+                        let mut trial = trial.clone();
+                        trial.insert(key.clone(), choice.clone());
+                        trial
+                    })
+                })
+                .collect();
+        }
+
+        Ok(trials)
+    }
+
+    /// random draws n trials, each parameter independently
+    pub fn random(&self, n: usize, rng: &mut StdRng) -> Vec<Map<String, Value>> {
+        (0..n)
+            .map(|_| {
+                self.dimensions
+                    .iter()
+                    .map(|(key, dimension)| (key.clone(), dimension.sample(rng)))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// run cross validates each candidate on the folds, jobs trials at once. Each worker builds its
+/// own datasets with dataset, as xgboost's handles can't be shared across threads, and takes
+/// the next trial until there are none left. The first error stops every worker.
+pub fn run<T, F>(
+    folds: &[(T, T)],
+    dataset: F,
+    candidates: &[xgb::Params],
+    objective: &str,
+    nclasses: u32,
+    jobs: usize,
+) -> Result<Vec<xgb::CrossValidation>, xgb::XgbError>
+where
+    T: Sync,
+    F: Fn(&T) -> Result<xgb::DMatrix, xgb::XgbError> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; candidates.len()]);
+    let error = Mutex::new(None);
+
+    let work = || -> Result<(), xgb::XgbError> {
+        let sets = folds
+            .iter()
+            .map(|(train, valid)| Ok((dataset(train)?, dataset(valid)?)))
+            .collect::<Result<Vec<_>, xgb::XgbError>>()?;
+
+        loop {
+            let trial = next.fetch_add(1, Ordering::SeqCst);
+            let params = match candidates.get(trial) {
+                Some(params) => params,
+                None => return Ok(()),
+            };
+
+            let result = xgb::cv(&sets, objective, nclasses, params)?;
+            eprintln!("trial {}/{} done", trial + 1, candidates.len());
+
+            results.lock().unwrap()[trial] = Some(result);
+        }
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                if let Err(e) = work() {
+                    next.store(candidates.len(), Ordering::SeqCst);
+                    error.lock().unwrap().get_or_insert(e);
+                }
+            });
+        }
+    });
+
+    if let Some(e) = error.into_inner().unwrap() {
+        return Err(e);
+    }
+
+    Ok(results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// Trial is the cross validated score of a candidate. A trial is scored at its best round with
+/// early stopping, as its model would be trained for that many rounds, and at its last round
+/// otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trial {
+    pub rounds: usize,
+    pub metric: String,
+    pub valid_mean: f64,
+    pub valid_std: f64,
+    pub train_mean: f64,
+}
+
+impl Trial {
+    pub fn new(result: &xgb::CrossValidation, early_stopping: bool) -> Self {
+        let round = if early_stopping {
+            result.best_iteration
+        } else {
+            result.valid.len() - 1
+        };
+
+        let (valid_mean, valid_std) = crate::mean_std(&result.valid[round]);
+        let (train_mean, _) = crate::mean_std(&result.train[round]);
+
+        Trial {
+            rounds: round + 1,
+            metric: result.metric.clone(),
+            valid_mean,
+            valid_std,
+            train_mean,
+        }
+    }
+}
+
+/// rank returns the indices of the trials best first, a NaN score last, ties in trial order
+pub fn rank(trials: &[Trial]) -> Vec<usize> {
+    let higher = trials
+        .first()
+        .map(|trial| xgb::higher_is_better(&trial.metric))
+        .unwrap_or(false);
+
+    let mut order: Vec<usize> = (0..trials.len()).collect();
+    order.sort_by(|a, b| {
+        let (a, b) = (trials[*a].valid_mean, trials[*b].valid_mean);
+        match (a.is_nan(), b.is_nan()) {
+            (false, false) if higher => b.partial_cmp(&a).unwrap(),
+            (false, false) => a.partial_cmp(&b).unwrap(),
+            (nan_a, nan_b) => nan_a.cmp(&nan_b),
+        }
+    });
+
+    order
+}
+
+/// leaderboard reports the trials in rank order, with the parameters drawn for each
+pub fn leaderboard(
+    space: &Space,
+    choices: &[Map<String, Value>],
+    trials: &[Trial],
+    order: &[usize],
+) -> Section {
+    let mut columns = vec!["rank", "trial"];
+    columns.extend(space.dimensions.iter().map(|(key, _)| key.as_str()));
+    columns.extend(["rounds", "metric", "valid_mean", "valid_std", "train_mean"]);
+
+    let mut leaderboard = Section::new("trials", &columns);
+    for (rank, index) in order.iter().enumerate() {
+        let trial = &trials[*index];
+
+        let mut row = vec![Value::from(rank + 1), Value::from(index + 1)];
+        row.extend(
+            space
+                .dimensions
+                .iter()
+                .map(|(key, _)| choices[*index][key].clone()),
+        );
+        row.extend([
+            Value::from(trial.rounds),
+            Value::from(trial.metric.as_str()),
+            Value::from(trial.valid_mean),
+            Value::from(trial.valid_std),
+            Value::from(trial.train_mean),
+        ]);
+
+        leaderboard.push(row);
+    }
+
+    leaderboard
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_grid() {
+        let space: Space = r#"{"max_depth": [3, 6], "eta": [0.1, 0.2, 0.3]}"#.parse().unwrap();
+        let trials = space.grid().unwrap();
+
+        assert_eq!(trials.len(), 6);
+        assert_eq!(
+            Value::from(trials[0].clone()),
+            serde_json::json!({"max_depth": 3, "eta": 0.1})
+        );
+        assert_eq!(
+            Value::from(trials[1].clone()),
+            serde_json::json!({"max_depth": 3, "eta": 0.2})
+        );
+        assert_eq!(
+            Value::from(trials[5].clone()),
+            serde_json::json!({"max_depth": 6, "eta": 0.3})
+        );
+        assert_eq!(
+            trials[0].keys().collect::<Vec<_>>(),
+            vec!["max_depth", "eta"]
+        );
+
+        let space: Space = r#"{"eta": {"min": 0.1, "max": 0.3}}"#.parse().unwrap();
+        assert!(space.grid().is_err());
+    }
+
+    #[test]
+    fn test_random() {
+        let space: Space = r#"{
+            "max_depth": {"min": 2, "max": 8},
+            "subsample": {"min": 0.5, "max": 1.0},
+            "booster": ["gbtree", "dart"]
+        }"#
+        .parse()
+        .unwrap();
+
+        let trials = space.random(200, &mut StdRng::seed_from_u64(7));
+        assert_eq!(trials.len(), 200);
+
+        for trial in &trials {
+            let depth = trial["max_depth"].as_u64().unwrap();
+            assert!((2..=8).contains(&depth));
+            let subsample = trial["subsample"].as_f64().unwrap();
+            assert!((0.5..=1.0).contains(&subsample));
+            assert!(["gbtree", "dart"].contains(&trial["booster"].as_str().unwrap()));
+        }
+
+        assert_eq!(space.random(200, &mut StdRng::seed_from_u64(7)), trials);
+        assert_ne!(space.random(200, &mut StdRng::seed_from_u64(8)), trials);
+    }
+
+    #[test]
+    fn test_log_range() {
+        let space: Space = r#"{"eta": {"min": 0.001, "max": 1.0, "log": true}}"#
+            .parse()
+            .unwrap();
+
+        let etas: Vec<f64> = space
+            .random(1000, &mut StdRng::seed_from_u64(1))
+            .iter()
+            .map(|trial| trial["eta"].as_f64().unwrap())
+            .collect();
+
+        assert!(etas.iter().all(|eta| (0.001..=1.0).contains(eta)));
+
+        // uniform in log space, a third of the draws fall in each decade
+        let below = etas.iter().filter(|eta| **eta < 0.01).count();
+        assert!((250..420).contains(&below), "{} draws below 0.01", below);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (input, error) in [
+            ("[1, 2]", "expected a json object"),
+            ("{}", "the search space has no parameters"),
+            (r#"{"eta": []}"#, "eta has no choices"),
+            (
+                r#"{"eta": 0.1}"#,
+                "eta must be a list of choices or a range",
+            ),
+            (r#"{"eta": {"min": 0.1}}"#, "eta needs a numeric max"),
+            (
+                r#"{"eta": {"min": 0.1, "max": 0.3, "step": 1}}"#,
+                "eta has an unknown field step",
+            ),
+            (
+                r#"{"eta": {"min": 0.3, "max": 0.1}}"#,
+                "eta needs min <= max",
+            ),
+            (
+                r#"{"eta": {"min": 0, "max": 1, "log": true}}"#,
+                "min > 0 for a log range",
+            ),
+            (
+                r#"{"eta": {"min": 0.1, "max": 1, "log": 1}}"#,
+                "eta log must be true or false",
+            ),
+        ] {
+            let e = input.parse::<Space>().unwrap_err();
+            assert!(e.contains(error), "{}: {}", input, e);
+        }
+
+        assert!("{".parse::<Space>().is_err());
+        assert_eq!("grid".parse::<Search>(), Ok(Search::Grid));
+        assert!("bayes".parse::<Search>().is_err());
+    }
+
+    #[test]
+    fn test_rank() {
+        let trial = |metric: &str, valid_mean: f64| Trial {
+            rounds: 10,
+            metric: metric.to_string(),
+            valid_mean,
+            valid_std: 0.0,
+            train_mean: 0.0,
+        };
+
+        // lower is better for rmse, a NaN score is last and ties keep trial order
+        let trials: Vec<Trial> = [0.5, f64::NAN, 0.2, 0.5, 0.9]
+            .iter()
+            .map(|x| trial("rmse", *x))
+            .collect();
+        assert_eq!(rank(&trials), [2, 0, 3, 4, 1]);
+
+        let trials: Vec<Trial> = [0.5, f64::NAN, 0.2, 0.9]
+            .iter()
+            .map(|x| trial("auc", *x))
+            .collect();
+        assert_eq!(rank(&trials), [3, 0, 2, 1]);
+
+        let space: Space = r#"{"max_depth": [3, 6]}"#.parse().unwrap();
+        let choices = space.grid().unwrap();
+        let trials = vec![trial("auc", 0.7), trial("auc", 0.8)];
+        let section = leaderboard(&space, &choices, &trials, &rank(&trials));
+        assert_eq!(section.columns[..3], ["rank", "trial", "max_depth"]);
+        assert_eq!(
+            section.rows[0][..3],
+            [Value::from(1), Value::from(2), Value::from(6)]
+        );
+    }
+}